@@ -0,0 +1,408 @@
+//Deterministic random trees and oracles used to cross-check the properties of `Tree`.
+//Every generator takes a seeded `Rng`, so a failing case can always be reproduced from its seed.
+
+use super::{Node, Tree};
+use crate::rng::Rng;
+use std::collections::VecDeque;
+
+fn shuffle<T>(rng: &mut Rng, values: &mut [T]) {
+    for i in (1..values.len()).rev() {
+        values.swap(i, rng.below(i + 1));
+    }
+}
+
+/// Uniformly random binary shape with `n` nodes and random keys in `0..max_key`.
+/// The shape is built with Rémy's algorithm, which grows a uniformly random full binary tree with `n`
+/// internal nodes; removing its leaves gives a uniformly random binary tree with `n` nodes.
+/// The node ids follow the preorder visit, the root has id 0.
+pub fn random_shape(rng: &mut Rng, n: usize, max_key: u32) -> Tree {
+    assert!(n > 0, "A tree has at least the root");
+
+    //Full binary tree on 2n + 1 nodes, children of a leaf are None.
+    let mut left: Vec<Option<usize>> = vec![None];
+    let mut right: Vec<Option<usize>> = vec![None];
+    let mut parent: Vec<Option<usize>> = vec![None];
+    let mut root = 0;
+
+    for _ in 0..n {
+        let x = rng.below(left.len());
+        let internal = left.len();
+        let leaf = internal + 1;
+        left.extend([None, None]);
+        right.extend([None, None]);
+        parent.extend([parent[x], Some(internal)]);
+
+        //The new internal node takes the place of x.
+        match parent[x] {
+            Some(p) if left[p] == Some(x) => left[p] = Some(internal),
+            Some(p) => right[p] = Some(internal),
+            None => root = internal,
+        }
+        parent[x] = Some(internal);
+
+        if rng.below(2) == 0 {
+            left[internal] = Some(x);
+            right[internal] = Some(leaf);
+        } else {
+            left[internal] = Some(leaf);
+            right[internal] = Some(x);
+        }
+    }
+
+    let mut tree = Tree { nodes: Vec::new() };
+    copy_internal(rng, &mut tree, &left, &right, root, max_key);
+    tree
+}
+
+//Copies the internal nodes of the full binary tree in preorder and returns the id of the copy of `x`.
+fn copy_internal(
+    rng: &mut Rng,
+    tree: &mut Tree,
+    left: &[Option<usize>],
+    right: &[Option<usize>],
+    x: usize,
+    max_key: u32,
+) -> Option<usize> {
+    //Leaves of the full tree are the missing children.
+    left[x]?;
+
+    let id = tree.nodes.len();
+    tree.nodes.push(Node::new(rng.next_u32() % max_key));
+    let id_left = copy_internal(rng, tree, left, right, left[x].unwrap(), max_key);
+    let id_right = copy_internal(rng, tree, left, right, right[x].unwrap(), max_key);
+    tree.nodes[id].id_left = id_left;
+    tree.nodes[id].id_right = id_right;
+    Some(id)
+}
+
+/// Random BST with `n` distinct keys, built inserting a random permutation of the keys.
+pub fn random_bst(rng: &mut Rng, n: usize) -> Tree {
+    assert!(n > 0, "A tree has at least the root");
+
+    //Distinct keys with random gaps between them.
+    let mut keys = Vec::with_capacity(n);
+    let mut key = 0;
+    for _ in 0..n {
+        key += 1 + rng.below(4) as u32;
+        keys.push(key);
+    }
+    shuffle(rng, &mut keys);
+
+    let mut tree = Tree::with_root(keys[0]);
+    for &key in keys[1..].iter() {
+        let mut id = 0;
+        loop {
+            let is_left = key < tree.nodes[id].key;
+            let child = if is_left {
+                tree.nodes[id].id_left
+            } else {
+                tree.nodes[id].id_right
+            };
+            match child {
+                Some(child_id) => id = child_id,
+                None => {
+                    tree.add_node(id, key, is_left);
+                    break;
+                }
+            }
+        }
+    }
+    tree
+}
+
+/// Random complete max heap with `n` nodes. The node ids are the indices of the array representation,
+/// so the children of `i` are `2i + 1` and `2i + 2`.
+pub fn random_heap(rng: &mut Rng, n: usize, max_key: u32) -> Tree {
+    assert!(n > 0, "A tree has at least the root");
+
+    let mut keys: Vec<u32> = (0..n).map(|_| rng.next_u32() % max_key).collect();
+    for i in (0..n / 2).rev() {
+        sift_down(&mut keys, i);
+    }
+
+    //Adding the nodes level by level gives them the same ids of the array.
+    let mut tree = Tree::with_root(keys[0]);
+    for (i, &key) in keys.iter().enumerate().skip(1) {
+        tree.add_node((i - 1) / 2, key, i % 2 == 1);
+    }
+    tree
+}
+
+fn sift_down(keys: &mut [u32], mut i: usize) {
+    loop {
+        let mut largest = i;
+        for child in [2 * i + 1, 2 * i + 2] {
+            if child < keys.len() && keys[child] > keys[largest] {
+                largest = child;
+            }
+        }
+        if largest == i {
+            return;
+        }
+        keys.swap(i, largest);
+        i = largest;
+    }
+}
+
+/// Random AVL-balanced tree of the given `height` (number of nodes on the longest path).
+/// The heights of the children of every node are chosen at random among the balanced combinations,
+/// the keys are assigned in order so the tree is also a BST.
+pub fn random_avl(rng: &mut Rng, height: usize) -> Tree {
+    assert!(height > 0, "A tree has at least the root");
+
+    let mut tree = Tree { nodes: Vec::new() };
+    avl_shape(rng, &mut tree, height);
+    let mut next_key = 1;
+    assign_inorder(&mut tree, Some(0), &mut next_key);
+    tree
+}
+
+fn avl_shape(rng: &mut Rng, tree: &mut Tree, height: usize) -> Option<usize> {
+    if height == 0 {
+        return None;
+    }
+
+    let id = tree.nodes.len();
+    tree.nodes.push(Node::new(0));
+
+    let (left_height, right_height) = match (height, rng.below(3)) {
+        (1, _) => (0, 0),
+        (_, 0) => (height - 1, height - 1),
+        (_, 1) => (height - 1, height - 2),
+        _ => (height - 2, height - 1),
+    };
+    tree.nodes[id].id_left = avl_shape(rng, tree, left_height);
+    tree.nodes[id].id_right = avl_shape(rng, tree, right_height);
+    Some(id)
+}
+
+fn assign_inorder(tree: &mut Tree, root: Option<usize>, next_key: &mut u32) {
+    if let Some(id) = root {
+        assign_inorder(tree, tree.nodes[id].id_left, next_key);
+        tree.nodes[id].key = *next_key;
+        *next_key += 1;
+        assign_inorder(tree, tree.nodes[id].id_right, next_key);
+    }
+}
+
+/// Degenerate tree where every node has only one child, on the left if `is_left` is `true`.
+/// The keys are decreasing along a left chain and increasing along a right chain, so the chain is a BST.
+pub fn chain(n: usize, is_left: bool) -> Tree {
+    assert!(n > 0, "A tree has at least the root");
+
+    let first_key = if is_left { n as u32 } else { 1 };
+    let mut tree = Tree::with_root(first_key);
+    for i in 1..n {
+        let key = if is_left {
+            first_key - i as u32
+        } else {
+            1 + i as u32
+        };
+        tree.add_node(i - 1, key, is_left);
+    }
+    tree
+}
+
+/// Oracle for `is_bst`: the keys collected with an inorder visit must be strictly increasing.
+pub fn oracle_is_bst(tree: &Tree, root: Option<usize>) -> bool {
+    let mut keys = Vec::new();
+    collect_inorder(tree, root, &mut keys);
+    keys.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+fn collect_inorder(tree: &Tree, root: Option<usize>, keys: &mut Vec<u32>) {
+    if let Some(id) = root {
+        collect_inorder(tree, tree.nodes[id].id_left, keys);
+        keys.push(tree.nodes[id].key);
+        collect_inorder(tree, tree.nodes[id].id_right, keys);
+    }
+}
+
+/// Oracle for `is_balanced`: the heights of the subtrees are computed from scratch for every node.
+pub fn oracle_is_balanced(tree: &Tree, root: Option<usize>) -> bool {
+    let Some(id) = root else {
+        return true;
+    };
+    let node = &tree.nodes[id];
+    height(tree, node.id_left).abs_diff(height(tree, node.id_right)) <= 1
+        && oracle_is_balanced(tree, node.id_left)
+        && oracle_is_balanced(tree, node.id_right)
+}
+
+fn height(tree: &Tree, root: Option<usize>) -> usize {
+    match root {
+        Some(id) => {
+            let node = &tree.nodes[id];
+            height(tree, node.id_left).max(height(tree, node.id_right)) + 1
+        }
+        None => 0,
+    }
+}
+
+/// Oracle for `is_complete`: in a level order visit no node can come after a missing child.
+pub fn oracle_is_complete(tree: &Tree, root: Option<usize>) -> bool {
+    let mut queue = VecDeque::from([root]);
+    let mut missing_seen = false;
+
+    while let Some(node) = queue.pop_front() {
+        match node {
+            Some(id) => {
+                if missing_seen {
+                    return false;
+                }
+                queue.push_back(tree.nodes[id].id_left);
+                queue.push_back(tree.nodes[id].id_right);
+            }
+            None => missing_seen = true,
+        }
+    }
+    true
+}
+
+/// Oracle for `is_heap`: the tree is complete and every key is greater or equal to the keys of its children.
+pub fn oracle_is_heap(tree: &Tree, root: Option<usize>) -> bool {
+    oracle_is_complete(tree, root) && keys_dominate(tree, root)
+}
+
+fn keys_dominate(tree: &Tree, root: Option<usize>) -> bool {
+    let Some(id) = root else {
+        return true;
+    };
+    let node = &tree.nodes[id];
+    [node.id_left, node.id_right]
+        .into_iter()
+        .flatten()
+        .all(|child| node.key >= tree.nodes[child].key && keys_dominate(tree, Some(child)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const CASES: u64 = 2000;
+
+    //Every check of `Tree` must agree with its oracle. The tree always contains all the nodes of the arena,
+    //which is what `is_complete` assumes when it compares the indices with the number of nodes.
+    fn assert_checks_match_oracles(tree: &Tree, seed: u64) {
+        let root = Some(0);
+        assert_eq!(
+            tree.is_bst(root),
+            oracle_is_bst(tree, root),
+            "seed {}",
+            seed
+        );
+        assert_eq!(
+            tree.is_balanced(root),
+            oracle_is_balanced(tree, root),
+            "seed {}",
+            seed
+        );
+        assert_eq!(
+            tree.is_complete(root, 0),
+            oracle_is_complete(tree, root),
+            "seed {}",
+            seed
+        );
+        assert_eq!(
+            tree.is_heap(root),
+            oracle_is_heap(tree, root),
+            "seed {}",
+            seed
+        );
+    }
+
+    #[test]
+    fn test_random_shapes() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let n = 1 + rng.below(40);
+            //Few distinct keys, so that some small trees are also BSTs or heaps by chance.
+            let max_key = if seed % 2 == 0 { 4 } else { 1000 };
+            let tree = random_shape(&mut rng, n, max_key);
+            assert_eq!(tree.nodes.len(), n);
+            assert_checks_match_oracles(&tree, seed);
+        }
+    }
+
+    #[test]
+    fn test_random_shapes_are_uniform() {
+        //There are 5 shapes with 3 nodes, each one should appear about 1/5 of the times.
+        let mut rng = Rng::new(42);
+        let mut counts = [0; 5];
+        let samples = 10_000;
+        for _ in 0..samples {
+            let tree = random_shape(&mut rng, 3, 10);
+            let root = &tree.nodes[0];
+            let shape = match (root.id_left, root.id_right) {
+                (Some(_), Some(_)) => 0,
+                (Some(child), None) if tree.nodes[child].id_left.is_some() => 1,
+                (Some(_), None) => 2,
+                (None, Some(child)) if tree.nodes[child].id_left.is_some() => 3,
+                _ => 4,
+            };
+            counts[shape] += 1;
+        }
+        for count in counts {
+            assert!((1700..2300).contains(&count), "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn test_random_bsts() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let n = 1 + rng.below(60);
+            let mut tree = random_bst(&mut rng, n);
+            assert!(tree.is_bst(Some(0)), "seed {}", seed);
+            assert_checks_match_oracles(&tree, seed);
+
+            //Moving a random key out of its place must be detected when it breaks the order.
+            let id = rng.below(n);
+            tree.nodes[id].key = rng.next_u32() % 300;
+            assert_checks_match_oracles(&tree, seed);
+        }
+    }
+
+    #[test]
+    fn test_random_heaps() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let n = 1 + rng.below(60);
+            let mut tree = random_heap(&mut rng, n, 100);
+            assert!(tree.is_heap(Some(0)), "seed {}", seed);
+            assert!(tree.is_complete(Some(0), 0), "seed {}", seed);
+            assert!(tree.is_balanced(Some(0)), "seed {}", seed);
+            assert_checks_match_oracles(&tree, seed);
+
+            let id = rng.below(n);
+            tree.nodes[id].key = rng.next_u32() % 100;
+            assert_checks_match_oracles(&tree, seed);
+        }
+    }
+
+    #[test]
+    fn test_random_avl_trees() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let height = 1 + rng.below(8);
+            let tree = random_avl(&mut rng, height);
+            assert!(tree.is_balanced(Some(0)), "seed {}", seed);
+            assert!(tree.is_bst(Some(0)), "seed {}", seed);
+            assert_checks_match_oracles(&tree, seed);
+        }
+    }
+
+    #[test]
+    fn test_chains() {
+        for n in 1..50 {
+            for is_left in [true, false] {
+                let tree = chain(n, is_left);
+                assert!(tree.is_bst(Some(0)));
+                assert_eq!(tree.is_balanced(Some(0)), n <= 2);
+                assert_eq!(tree.is_complete(Some(0), 0), n == 1 || (n == 2 && is_left));
+                assert_checks_match_oracles(&tree, n as u64);
+            }
+        }
+    }
+}
@@ -1,3 +1,17 @@
+#[cfg(test)]
+mod generators;
+mod nary;
+mod parallel;
+mod persistent;
+mod rng;
+mod splay;
+mod treap;
+
+use nary::NaryTree;
+use persistent::PersistentTree;
+use splay::SplayTree;
+use treap::Treap;
+
 #[derive(Debug)]
 struct Node {
     key: u32,
@@ -194,6 +208,7 @@ impl Tree {
     }
 }
 
+#[allow(clippy::items_after_test_module)]
 #[cfg(test)]
 mod tests {
 
@@ -489,6 +504,49 @@ fn main() {
     tree.is_balanced(Some(0));
     tree.is_bst(Some(0));
     tree.sum();
+    tree.par_is_heap(Some(0));
+    tree.par_is_balanced(Some(0));
+    tree.par_is_bst(Some(0));
+
+    let mut history = PersistentTree::new();
+    history.insert(10);
+    history.insert(5);
+    for version in 0..history.versions() {
+        history.keys(version);
+        history.is_bst(version);
+        history.is_balanced(version);
+        history.sum(version);
+    }
+    history.arena_len();
+
+    let mut nary = NaryTree::with_root(10);
+    nary.add_child(0, 5);
+    nary.preorder();
+    nary.postorder();
+    nary.level_order();
+    nary.height();
+    nary.is_balanced();
+    nary.sum();
+    NaryTree::from_binary(&nary.to_binary());
+
+    let mut splay = SplayTree::new(true);
+    let mut splay_root = None;
+    splay.insert(&mut splay_root, 10);
+    splay.contains(&mut splay_root, 10);
+    let (left, right) = splay.split(splay_root, 10);
+    splay_root = splay.merge(left, right);
+    splay.keys(splay_root);
+    splay.is_bst(splay_root);
+
+    let mut treap = Treap::new(0);
+    let mut treap_root = None;
+    treap.insert(&mut treap_root, 10);
+    let (left, right) = treap.split(treap_root, 10);
+    treap_root = treap.merge(left, right);
+    treap.keys(treap_root);
+    treap.is_bst(treap_root);
+    treap.is_heap_ordered(treap_root);
+    treap.height(treap_root);
 }
 
 //Author Massimiliano Baglioni
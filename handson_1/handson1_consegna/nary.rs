@@ -0,0 +1,303 @@
+use super::{Node, Tree};
+use std::collections::VecDeque;
+
+#[derive(Debug)]
+struct NaryNode {
+    key: u32,
+    children: Vec<usize>,
+}
+
+impl NaryNode {
+    fn new(key: u32) -> Self {
+        Self {
+            key,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// This is the n-ary version of `Tree`.
+/// Every node has an implicit id, which is its position on the vector `nodes`, and any number of children.
+/// The ids of the children are stored in `children` in the order they have been added.
+///
+/// The tree can be converted to and from a binary `Tree` with the left-child/right-sibling encoding:
+/// the left child of a node is its first child and the right child is its next sibling.
+/// The ids are the same in both representations, so the conversion is lossless.
+/// On the encoded form `sum` gives the same result, and `is_bst` checks that the keys
+/// of the n-ary tree are strictly increasing in postorder, since the inorder visit of the encoding
+/// is the postorder visit of the n-ary tree. The other checks depend on the shape of the encoding.
+#[derive(Debug)]
+pub struct NaryTree {
+    nodes: Vec<NaryNode>,
+}
+
+impl NaryTree {
+    pub fn with_root(key: u32) -> Self {
+        Self {
+            nodes: vec![NaryNode::new(key)],
+        }
+    }
+
+    /// Adds a child with the specified `key` to the node with `parent_id` and returns the id of the new node.
+    /// The new node is the last child of `parent_id`.
+    ///
+    /// # Panics
+    /// Panics if the `parent_id` does not exist.
+    pub fn add_child(&mut self, parent_id: usize, key: u32) -> usize {
+        assert!(
+            parent_id < self.nodes.len(),
+            "Parent node id does not exist"
+        );
+
+        let child_id = self.nodes.len();
+        self.nodes.push(NaryNode::new(key));
+        self.nodes[parent_id].children.push(child_id);
+
+        child_id
+    }
+
+    /// Returns the sum of all the keys in the tree
+    pub fn sum(&self) -> u32 {
+        self.nodes.iter().map(|node| node.key).sum()
+    }
+
+    /// Returns the number of nodes on the longest path from the root to a leaf.
+    pub fn height(&self) -> usize {
+        self.height_rec(0)
+    }
+
+    fn height_rec(&self, id: usize) -> usize {
+        self.nodes[id]
+            .children
+            .iter()
+            .map(|&child| self.height_rec(child))
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    /// A tree is balanced if for each of its nodes the heights of the children differ at most by 1.
+    /// Leaves and nodes with a single child are always balanced.
+    pub fn is_balanced(&self) -> bool {
+        self.is_balanced_rec(0).is_some()
+    }
+
+    //Returns the height of the subtree if it is balanced, None otherwise.
+    fn is_balanced_rec(&self, id: usize) -> Option<usize> {
+        let mut min_height = usize::MAX;
+        let mut max_height = 0;
+
+        for &child in self.nodes[id].children.iter() {
+            let height = self.is_balanced_rec(child)?;
+            min_height = min_height.min(height);
+            max_height = max_height.max(height);
+        }
+
+        if max_height > 0 && max_height - min_height > 1 {
+            return None;
+        }
+        Some(max_height + 1)
+    }
+
+    /// Returns the keys in preorder: every node comes before its children.
+    pub fn preorder(&self) -> Vec<u32> {
+        let mut keys = Vec::with_capacity(self.nodes.len());
+        self.preorder_rec(0, &mut keys);
+        keys
+    }
+
+    fn preorder_rec(&self, id: usize, keys: &mut Vec<u32>) {
+        keys.push(self.nodes[id].key);
+        for &child in self.nodes[id].children.iter() {
+            self.preorder_rec(child, keys);
+        }
+    }
+
+    /// Returns the keys in postorder: every node comes after its children.
+    pub fn postorder(&self) -> Vec<u32> {
+        let mut keys = Vec::with_capacity(self.nodes.len());
+        self.postorder_rec(0, &mut keys);
+        keys
+    }
+
+    fn postorder_rec(&self, id: usize, keys: &mut Vec<u32>) {
+        for &child in self.nodes[id].children.iter() {
+            self.postorder_rec(child, keys);
+        }
+        keys.push(self.nodes[id].key);
+    }
+
+    /// Returns the keys level by level, from left to right.
+    pub fn level_order(&self) -> Vec<u32> {
+        let mut keys = Vec::with_capacity(self.nodes.len());
+        let mut queue = VecDeque::from([0]);
+
+        while let Some(id) = queue.pop_front() {
+            keys.push(self.nodes[id].key);
+            queue.extend(self.nodes[id].children.iter());
+        }
+        keys
+    }
+
+    /// Encodes the tree as a binary `Tree` with the left-child/right-sibling encoding.
+    pub fn to_binary(&self) -> Tree {
+        let mut nodes: Vec<Node> = self.nodes.iter().map(|node| Node::new(node.key)).collect();
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            nodes[id].id_left = node.children.first().copied();
+            for siblings in node.children.windows(2) {
+                nodes[siblings[0]].id_right = Some(siblings[1]);
+            }
+        }
+
+        Tree { nodes }
+    }
+
+    /// Decodes a binary `Tree` built with the left-child/right-sibling encoding.
+    ///
+    /// # Panics
+    /// Panics if the root has a right child, since the root of an n-ary tree has no siblings.
+    pub fn from_binary(tree: &Tree) -> Self {
+        assert!(
+            tree.nodes[0].id_right.is_none(),
+            "The root of the encoding cannot have a sibling"
+        );
+
+        let mut nodes: Vec<NaryNode> = tree
+            .nodes
+            .iter()
+            .map(|node| NaryNode::new(node.key))
+            .collect();
+
+        for (id, node) in tree.nodes.iter().enumerate() {
+            let mut child = node.id_left;
+            while let Some(child_id) = child {
+                nodes[id].children.push(child_id);
+                child = tree.nodes[child_id].id_right;
+            }
+        }
+
+        Self { nodes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    //       1
+    //   /   |   \
+    //  2    3    4
+    //  |        / \
+    //  5       6   7
+    //              |
+    //              8
+    fn example() -> NaryTree {
+        let mut tree = NaryTree::with_root(1);
+        tree.add_child(0, 2); //id 1
+        tree.add_child(0, 3); //id 2
+        tree.add_child(0, 4); //id 3
+        tree.add_child(1, 5); //id 4
+        tree.add_child(3, 6); //id 5
+        tree.add_child(3, 7); //id 6
+        tree.add_child(6, 8); //id 7
+        tree
+    }
+
+    #[test]
+    fn test_traversals() {
+        let tree = example();
+        assert_eq!(tree.preorder(), vec![1, 2, 5, 3, 4, 6, 7, 8]);
+        assert_eq!(tree.postorder(), vec![5, 2, 3, 6, 8, 7, 4, 1]);
+        assert_eq!(tree.level_order(), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_sum_and_height() {
+        let mut tree = NaryTree::with_root(10);
+        assert_eq!(tree.sum(), 10);
+        assert_eq!(tree.height(), 1);
+
+        let tree_example = example();
+        assert_eq!(tree_example.sum(), 36);
+        assert_eq!(tree_example.height(), 4);
+
+        tree.add_child(0, 5);
+        assert_eq!(tree.sum(), 15);
+        assert_eq!(tree.height(), 2);
+    }
+
+    #[test]
+    fn test_is_balanced() {
+        //Root only tree.
+        let mut tree = NaryTree::with_root(1);
+        assert!(tree.is_balanced());
+
+        //A chain is balanced since every node has a single child.
+        tree.add_child(0, 2);
+        tree.add_child(1, 3);
+        assert!(tree.is_balanced());
+
+        //Children heights 2 and 1.
+        tree = NaryTree::with_root(1);
+        tree.add_child(0, 2);
+        tree.add_child(0, 3);
+        tree.add_child(1, 4);
+        assert!(tree.is_balanced());
+
+        //Children heights 3 and 1.
+        tree.add_child(3, 5);
+        assert!(!tree.is_balanced());
+
+        //Children of the root have heights 2, 1 and 3.
+        assert!(!example().is_balanced());
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let tree = example();
+        let binary = tree.to_binary();
+
+        //Left child is the first child, right child is the next sibling.
+        assert_eq!(binary.nodes[0].id_left, Some(1));
+        assert_eq!(binary.nodes[0].id_right, None);
+        assert_eq!(binary.nodes[1].id_right, Some(2));
+        assert_eq!(binary.nodes[2].id_right, Some(3));
+        assert_eq!(binary.nodes[3].id_left, Some(5));
+        assert_eq!(binary.nodes[5].id_right, Some(6));
+
+        assert_eq!(binary.sum(), tree.sum());
+
+        let decoded = NaryTree::from_binary(&binary);
+        assert_eq!(decoded.preorder(), tree.preorder());
+        assert_eq!(decoded.level_order(), tree.level_order());
+        for (id, node) in tree.nodes.iter().enumerate() {
+            assert_eq!(decoded.nodes[id].key, node.key);
+            assert_eq!(decoded.nodes[id].children, node.children);
+        }
+    }
+
+    #[test]
+    fn test_checks_on_encoding() {
+        //Postorder 5 2 3 6 8 7 4 1 is not increasing.
+        assert!(!example().to_binary().is_bst(Some(0)));
+
+        //Postorder 1 2 3 4 5 is increasing.
+        let mut tree = NaryTree::with_root(5);
+        tree.add_child(0, 2);
+        tree.add_child(0, 4);
+        tree.add_child(1, 1);
+        tree.add_child(2, 3);
+        assert_eq!(tree.postorder(), vec![1, 2, 3, 4, 5]);
+        assert!(tree.to_binary().is_bst(Some(0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_binary_root_with_sibling() {
+        let mut binary = Tree::with_root(1);
+        binary.add_node(0, 2, false);
+        NaryTree::from_binary(&binary);
+    }
+}
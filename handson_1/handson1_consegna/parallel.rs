@@ -0,0 +1,292 @@
+use super::Tree;
+use std::thread;
+
+/// Trees with fewer nodes than this are checked with the sequential functions,
+/// spawning threads is not worth it below this size.
+const PARALLEL_THRESHOLD: usize = 1 << 16;
+
+/// Worker threads recurse on whole subtrees, so they get a bigger stack than the default 2MiB.
+const WORKER_STACK_SIZE: usize = 64 << 20;
+
+/// Summary of a subtree used to combine the results of the BST check.
+/// `min` and `max` are meaningful only if `is_bst` is `true`, they are `None` for an empty subtree.
+#[derive(Clone, Copy)]
+struct BstSummary {
+    is_bst: bool,
+    min: Option<u32>,
+    max: Option<u32>,
+}
+
+/// Parallel versions of the property checks.
+/// The tree is split at the subtrees near the root: every subtree below the split depth is checked
+/// sequentially by its own thread, then the summaries (min/max key, height, heap validity) are combined
+/// going up to the root. The results are exactly the same of the sequential functions.
+impl Tree {
+    pub fn par_is_bst(&self, root: Option<usize>) -> bool {
+        self.par_is_bst_with(root, PARALLEL_THRESHOLD)
+    }
+
+    pub fn par_is_balanced(&self, root_id: Option<usize>) -> bool {
+        self.par_is_balanced_with(root_id, PARALLEL_THRESHOLD)
+    }
+
+    pub fn par_is_heap(&self, root: Option<usize>) -> bool {
+        self.par_is_heap_with(root, PARALLEL_THRESHOLD)
+    }
+
+    fn par_is_bst_with(&self, root: Option<usize>, threshold: usize) -> bool {
+        if self.nodes.len() < threshold {
+            return self.is_bst(root);
+        }
+        self.bst_summary(root, split_depth()).is_bst
+    }
+
+    fn par_is_balanced_with(&self, root_id: Option<usize>, threshold: usize) -> bool {
+        if self.nodes.len() < threshold {
+            return self.is_balanced(root_id);
+        }
+        self.par_balanced_rec(root_id, split_depth()) != 0
+    }
+
+    fn par_is_heap_with(&self, root: Option<usize>, threshold: usize) -> bool {
+        if self.nodes.len() < threshold {
+            return self.is_heap(root);
+        }
+        let depth = split_depth();
+        let (complete, heap) = join(
+            || self.par_is_complete(root, 0, depth),
+            || self.par_is_heap_util(root, depth),
+        );
+        complete && heap
+    }
+
+    //Below the split depth the subtree is checked with the sequential is_bst, its min and max are the leftmost and rightmost keys.
+    fn bst_summary(&self, root: Option<usize>, depth: usize) -> BstSummary {
+        let Some(id) = root else {
+            return BstSummary {
+                is_bst: true,
+                min: None,
+                max: None,
+            };
+        };
+
+        if depth == 0 {
+            return BstSummary {
+                is_bst: self.is_bst(root),
+                min: Some(self.nodes[self.leftmost(id)].key),
+                max: Some(self.nodes[self.rightmost(id)].key),
+            };
+        }
+
+        let node = &self.nodes[id];
+        let (left, right) = join(
+            || self.bst_summary(node.id_left, depth - 1),
+            || self.bst_summary(node.id_right, depth - 1),
+        );
+
+        //Same strict inequalities used by is_bst_inorder: every key of the left subtree is smaller than the node key and every key on the right is greater.
+        let is_bst = left.is_bst
+            && right.is_bst
+            && left.max.is_none_or(|max| max < node.key)
+            && right.min.is_none_or(|min| node.key < min);
+
+        BstSummary {
+            is_bst,
+            min: left.min.or(Some(node.key)),
+            max: right.max.or(Some(node.key)),
+        }
+    }
+
+    fn leftmost(&self, mut id: usize) -> usize {
+        while let Some(left) = self.nodes[id].id_left {
+            id = left;
+        }
+        id
+    }
+
+    fn rightmost(&self, mut id: usize) -> usize {
+        while let Some(right) = self.nodes[id].id_right {
+            id = right;
+        }
+        id
+    }
+
+    //Same return convention of is_balanced_rec: height + 1 of the subtree if it is balanced, 0 otherwise.
+    fn par_balanced_rec(&self, root_id: Option<usize>, depth: usize) -> i32 {
+        let Some(id) = root_id else {
+            return 1;
+        };
+
+        if depth == 0 {
+            return self.is_balanced_rec(root_id);
+        }
+
+        let (left_height, right_height) = join(
+            || self.par_balanced_rec(self.nodes[id].id_left, depth - 1),
+            || self.par_balanced_rec(self.nodes[id].id_right, depth - 1),
+        );
+
+        if left_height == 0 || right_height == 0 || (left_height - right_height).abs() > 1 {
+            return 0;
+        }
+        std::cmp::max(left_height, right_height) + 1
+    }
+
+    fn par_is_complete(&self, root: Option<usize>, index: usize, depth: usize) -> bool {
+        let Some(root_id) = root else {
+            return true;
+        };
+
+        if depth == 0 {
+            return self.is_complete(root, index);
+        }
+        if index >= self.nodes.len() {
+            return false;
+        }
+
+        let (left, right) = join(
+            || self.par_is_complete(self.nodes[root_id].id_left, 2 * index + 1, depth - 1),
+            || self.par_is_complete(self.nodes[root_id].id_right, 2 * index + 2, depth - 1),
+        );
+        left && right
+    }
+
+    //Mirrors is_heap_util: only a node with both children keeps recursing, the other cases are answered directly.
+    fn par_is_heap_util(&self, root: Option<usize>, depth: usize) -> bool {
+        let Some(root_id) = root else {
+            return true;
+        };
+
+        let node = &self.nodes[root_id];
+        let (Some(id_left), Some(id_right)) = (node.id_left, node.id_right) else {
+            return self.is_heap_util(root);
+        };
+
+        if depth == 0 {
+            return self.is_heap_util(root);
+        }
+
+        if node.key < self.nodes[id_left].key || node.key < self.nodes[id_right].key {
+            return false;
+        }
+
+        let (left, right) = join(
+            || self.par_is_heap_util(node.id_left, depth - 1),
+            || self.par_is_heap_util(node.id_right, depth - 1),
+        );
+        left && right
+    }
+}
+
+//Number of levels to split so that there are at least as many subtrees as available threads.
+fn split_depth() -> usize {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    threads.next_power_of_two().trailing_zeros() as usize
+}
+
+//Runs the two closures in parallel, the first one on a new scoped thread and the second one on the current thread.
+fn join<A, B, FA, FB>(first: FA, second: FB) -> (A, B)
+where
+    A: Send,
+    FA: FnOnce() -> A + Send,
+    FB: FnOnce() -> B,
+{
+    thread::scope(|scope| {
+        let handle = thread::Builder::new()
+            .stack_size(WORKER_STACK_SIZE)
+            .spawn_scoped(scope, first)
+            .expect("Failed to spawn a worker thread");
+        let second = second();
+        (handle.join().expect("Worker thread panicked"), second)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    //Balanced BST with keys 1..=n, node ids follow a preorder visit.
+    fn complete_bst(n: usize) -> Tree {
+        let keys: Vec<u32> = (1..=n as u32).collect();
+        let mut tree = Tree::with_root(0);
+        tree.nodes.clear();
+        build_bst(&mut tree, &keys);
+        tree
+    }
+
+    fn build_bst(tree: &mut Tree, keys: &[u32]) -> Option<usize> {
+        if keys.is_empty() {
+            return None;
+        }
+        let mid = keys.len() / 2;
+        let id = tree.nodes.len();
+        tree.nodes.push(crate::Node::new(keys[mid]));
+        tree.nodes[id].id_left = build_bst(tree, &keys[..mid]);
+        tree.nodes[id].id_right = build_bst(tree, &keys[mid + 1..]);
+        Some(id)
+    }
+
+    fn assert_same_results(tree: &Tree) {
+        for root in [None, Some(0)] {
+            assert_eq!(tree.par_is_bst_with(root, 0), tree.is_bst(root));
+            assert_eq!(tree.par_is_balanced_with(root, 0), tree.is_balanced(root));
+            assert_eq!(tree.par_is_heap_with(root, 0), tree.is_heap(root));
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        //Valid BST, balanced.
+        let mut tree = complete_bst(1000);
+        assert!(tree.par_is_bst_with(Some(0), 0));
+        assert!(tree.par_is_balanced_with(Some(0), 0));
+        assert_same_results(&tree);
+
+        //Break the BST property deep in the tree.
+        let leaf = tree.rightmost(tree.nodes[0].id_left.unwrap());
+        tree.nodes[leaf].key = tree.nodes[0].key;
+        assert!(!tree.par_is_bst_with(Some(0), 0));
+        assert_same_results(&tree);
+
+        //Valid heap.
+        let mut tree = Tree::with_root(100);
+        tree.add_node(0, 19, true);
+        tree.add_node(0, 36, false);
+        tree.add_node(1, 17, true);
+        tree.add_node(1, 3, false);
+        tree.add_node(2, 25, true);
+        tree.add_node(2, 1, false);
+        tree.add_node(3, 2, true);
+        tree.add_node(3, 7, false);
+        assert!(tree.par_is_heap_with(Some(0), 0));
+        assert_same_results(&tree);
+
+        //Violating the max property of the heap on a leaf.
+        tree.nodes[8].key = 50;
+        assert!(!tree.par_is_heap_with(Some(0), 0));
+        assert_same_results(&tree);
+
+        //Not complete, not balanced.
+        let mut tree = Tree::with_root(20);
+        tree.add_node(0, 10, true);
+        tree.add_node(1, 8, true);
+        tree.add_node(2, 5, true);
+        assert!(!tree.par_is_balanced_with(Some(0), 0));
+        assert!(!tree.par_is_heap_with(Some(0), 0));
+        assert_same_results(&tree);
+
+        //Right child only.
+        let mut tree = Tree::with_root(10);
+        tree.add_node(0, 20, false);
+        assert_same_results(&tree);
+    }
+
+    #[test]
+    fn test_parallel_above_threshold() {
+        let tree = complete_bst(PARALLEL_THRESHOLD * 2);
+        assert!(tree.par_is_bst(Some(0)));
+        assert!(tree.par_is_balanced(Some(0)));
+        assert_eq!(tree.par_is_heap(Some(0)), tree.is_heap(Some(0)));
+    }
+}
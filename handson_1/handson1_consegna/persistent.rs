@@ -0,0 +1,199 @@
+use super::{Node, Tree};
+
+/// Persistent version of the BST: every insert keeps the previous versions untouched.
+/// The nodes are never modified after they are pushed in the arena, an insert copies only the nodes
+/// on the path from the root to the new leaf and shares every other subtree with the previous version.
+/// Every version is identified by its root, so all the checks of `Tree` that take a root work on any version.
+#[derive(Debug)]
+pub struct PersistentTree {
+    tree: Tree,
+    roots: Vec<Option<usize>>,
+}
+
+impl PersistentTree {
+    /// Creates a tree whose only version (version 0) is the empty tree.
+    pub fn new() -> Self {
+        Self {
+            tree: Tree { nodes: Vec::new() },
+            roots: vec![None],
+        }
+    }
+
+    /// Inserts `key` in the latest version and returns the id of the new root.
+    /// The new version is pushed after all the others. Inserting a key that is already present
+    /// creates a version that shares the root of the previous one.
+    pub fn insert(&mut self, key: u32) -> usize {
+        let root = self.insert_rec(self.latest_root(), key);
+        self.roots.push(Some(root));
+        root
+    }
+
+    //Returns the id of the node that replaces `node_id` in the new version. The node is copied only if its subtree changed.
+    fn insert_rec(&mut self, node_id: Option<usize>, key: u32) -> usize {
+        let Some(id) = node_id else {
+            self.tree.nodes.push(Node::new(key));
+            return self.tree.nodes.len() - 1;
+        };
+
+        let node = &self.tree.nodes[id];
+        let (node_key, id_left, id_right) = (node.key, node.id_left, node.id_right);
+
+        let copy = if key < node_key {
+            let new_left = self.insert_rec(id_left, key);
+            if Some(new_left) == id_left {
+                return id;
+            }
+            Node {
+                key: node_key,
+                id_left: Some(new_left),
+                id_right,
+            }
+        } else if key > node_key {
+            let new_right = self.insert_rec(id_right, key);
+            if Some(new_right) == id_right {
+                return id;
+            }
+            Node {
+                key: node_key,
+                id_left,
+                id_right: Some(new_right),
+            }
+        } else {
+            //Key already present, nothing to copy.
+            return id;
+        };
+
+        self.tree.nodes.push(copy);
+        self.tree.nodes.len() - 1
+    }
+
+    /// Returns the number of versions, the empty tree included.
+    pub fn versions(&self) -> usize {
+        self.roots.len()
+    }
+
+    /// Returns the root of the given `version`, `None` if that version is the empty tree.
+    ///
+    /// # Panics
+    /// Panics if the `version` does not exist.
+    pub fn root(&self, version: usize) -> Option<usize> {
+        assert!(version < self.roots.len(), "Version does not exist");
+        self.roots[version]
+    }
+
+    fn latest_root(&self) -> Option<usize> {
+        self.roots[self.roots.len() - 1]
+    }
+
+    /// Returns the number of nodes stored in the arena, shared by all the versions.
+    pub fn arena_len(&self) -> usize {
+        self.tree.nodes.len()
+    }
+
+    pub fn sum(&self, version: usize) -> u32 {
+        self.tree.rec_sum(self.root(version))
+    }
+
+    pub fn is_bst(&self, version: usize) -> bool {
+        self.tree.is_bst(self.root(version))
+    }
+
+    pub fn is_balanced(&self, version: usize) -> bool {
+        self.tree.is_balanced(self.root(version))
+    }
+
+    /// Returns the keys of the given `version` in order.
+    pub fn keys(&self, version: usize) -> Vec<u32> {
+        let mut keys = Vec::new();
+        self.keys_inorder(self.root(version), &mut keys);
+        keys
+    }
+
+    fn keys_inorder(&self, node_id: Option<usize>, keys: &mut Vec<u32>) {
+        if let Some(id) = node_id {
+            let node = &self.tree.nodes[id];
+            self.keys_inorder(node.id_left, keys);
+            keys.push(node.key);
+            self.keys_inorder(node.id_right, keys);
+        }
+    }
+}
+
+impl Default for PersistentTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_versions_are_preserved() {
+        let mut tree = PersistentTree::new();
+        assert_eq!(tree.sum(0), 0);
+        assert!(tree.is_bst(0));
+        assert!(tree.is_balanced(0));
+
+        let keys = [40, 30, 50, 25, 35, 45, 60, 20];
+        for key in keys {
+            tree.insert(key);
+        }
+        assert_eq!(tree.versions(), keys.len() + 1);
+
+        //Every version contains exactly the keys inserted before it.
+        for version in 0..tree.versions() {
+            let mut expected = keys[..version].to_vec();
+            expected.sort();
+            assert_eq!(tree.keys(version), expected);
+            assert_eq!(tree.sum(version), expected.iter().sum::<u32>());
+            assert!(tree.is_bst(version));
+        }
+
+        //Full tree with 7 nodes is balanced, adding 20 on the leftmost path keeps it balanced.
+        assert!(tree.is_balanced(7));
+        assert!(tree.is_balanced(8));
+        //Only 40 and 30 were inserted in version 2.
+        assert!(tree.is_balanced(2));
+    }
+
+    #[test]
+    fn test_path_copying_shares_nodes() {
+        let mut tree = PersistentTree::new();
+        tree.insert(40);
+        tree.insert(30);
+        tree.insert(50);
+        assert_eq!(tree.arena_len(), 1 + 2 + 2);
+
+        //Inserting on the left copies the root and 30, the subtree of 50 is shared.
+        let old_root = tree.root(3).unwrap();
+        let new_root = tree.insert(25);
+        assert_eq!(tree.arena_len(), 5 + 3);
+        assert_ne!(old_root, new_root);
+        assert_eq!(
+            tree.tree.nodes[old_root].id_right,
+            tree.tree.nodes[new_root].id_right
+        );
+
+        //Inserting a key already present does not copy anything.
+        let root = tree.insert(30);
+        assert_eq!(root, new_root);
+        assert_eq!(tree.arena_len(), 8);
+        assert_eq!(tree.keys(5), tree.keys(4));
+    }
+
+    #[test]
+    fn test_unbalanced_history() {
+        let mut tree = PersistentTree::new();
+        for key in [10, 20, 30] {
+            tree.insert(key);
+        }
+        assert!(tree.is_balanced(2));
+        assert!(!tree.is_balanced(3));
+        assert!(tree.is_bst(3));
+        assert_eq!(tree.sum(3), 60);
+        assert_eq!(tree.sum(1), 10);
+    }
+}
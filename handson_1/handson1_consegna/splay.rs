@@ -0,0 +1,315 @@
+use super::{Node, Tree};
+
+/// Splay tree stored in the same arena of `Tree`.
+/// Every access moves the accessed key (or the last node visited) to the root with top-down splaying,
+/// this gives amortised O(log n) time per operation. The nodes have no parent pointer, so the top-down
+/// version is the one that fits the arena layout.
+///
+/// The arena can hold several ordered sets at the same time, each one identified by its root:
+/// `split` and `merge` only relink nodes and never copy them.
+#[derive(Debug)]
+pub struct SplayTree {
+    tree: Tree,
+    splay_on_lookup: bool,
+}
+
+impl SplayTree {
+    /// Creates an empty arena. If `splay_on_lookup` is `true` also `contains` moves the key to the root,
+    /// otherwise lookups are plain BST searches and only inserts, splits and merges restructure the tree.
+    pub fn new(splay_on_lookup: bool) -> Self {
+        Self {
+            tree: Tree { nodes: Vec::new() },
+            splay_on_lookup,
+        }
+    }
+
+    /// Inserts `key` in the set rooted at `root` and updates `root`.
+    /// Returns `false` if the key was already present.
+    pub fn insert(&mut self, root: &mut Option<usize>, key: u32) -> bool {
+        let Some(id) = *root else {
+            *root = Some(self.new_node(key));
+            return true;
+        };
+
+        let t = self.splay(id, key);
+        *root = Some(t);
+        if self.tree.nodes[t].key == key {
+            return false;
+        }
+
+        //The root is the predecessor or the successor of the new key, it becomes a child of the new node.
+        let new_id = self.new_node(key);
+        if key < self.tree.nodes[t].key {
+            self.tree.nodes[new_id].id_left = self.tree.nodes[t].id_left.take();
+            self.tree.nodes[new_id].id_right = Some(t);
+        } else {
+            self.tree.nodes[new_id].id_right = self.tree.nodes[t].id_right.take();
+            self.tree.nodes[new_id].id_left = Some(t);
+        }
+        *root = Some(new_id);
+        true
+    }
+
+    /// Returns `true` if `key` is in the set rooted at `root`.
+    /// With `splay_on_lookup` the last node visited becomes the root and `root` is updated.
+    pub fn contains(&mut self, root: &mut Option<usize>, key: u32) -> bool {
+        let Some(id) = *root else {
+            return false;
+        };
+
+        if self.splay_on_lookup {
+            let t = self.splay(id, key);
+            *root = Some(t);
+            return self.tree.nodes[t].key == key;
+        }
+
+        let mut node = Some(id);
+        while let Some(id) = node {
+            let current = &self.tree.nodes[id];
+            if key == current.key {
+                return true;
+            }
+            node = if key < current.key {
+                current.id_left
+            } else {
+                current.id_right
+            };
+        }
+        false
+    }
+
+    /// Splits the set rooted at `root` in the keys smaller than `key` and the keys greater or equal to `key`.
+    /// Returns the roots of the two sets.
+    pub fn split(&mut self, root: Option<usize>, key: u32) -> (Option<usize>, Option<usize>) {
+        let Some(id) = root else {
+            return (None, None);
+        };
+
+        let t = self.splay(id, key);
+        if self.tree.nodes[t].key < key {
+            let right = self.tree.nodes[t].id_right.take();
+            (Some(t), right)
+        } else {
+            let left = self.tree.nodes[t].id_left.take();
+            (left, Some(t))
+        }
+    }
+
+    /// Joins two sets and returns the root of the result.
+    /// Every key of the set rooted at `left` must be smaller than every key of the set rooted at `right`.
+    pub fn merge(&mut self, left: Option<usize>, right: Option<usize>) -> Option<usize> {
+        let Some(id) = left else {
+            return right;
+        };
+
+        //After splaying the maximum is the root and has no right child.
+        let t = self.splay(id, u32::MAX);
+        self.tree.nodes[t].id_right = right;
+        Some(t)
+    }
+
+    /// Returns the keys of the set rooted at `root` in order.
+    pub fn keys(&self, root: Option<usize>) -> Vec<u32> {
+        let mut keys = Vec::new();
+        let mut stack = Vec::new();
+        let mut node = root;
+
+        while node.is_some() || !stack.is_empty() {
+            while let Some(id) = node {
+                stack.push(id);
+                node = self.tree.nodes[id].id_left;
+            }
+            let id = stack.pop().unwrap();
+            keys.push(self.tree.nodes[id].key);
+            node = self.tree.nodes[id].id_right;
+        }
+        keys
+    }
+
+    pub fn is_bst(&self, root: Option<usize>) -> bool {
+        self.tree.is_bst(root)
+    }
+
+    fn new_node(&mut self, key: u32) -> usize {
+        self.tree.nodes.push(Node::new(key));
+        self.tree.nodes.len() - 1
+    }
+
+    //Top-down splay. The nodes smaller than key are linked in a left tree and the greater ones in a right tree,
+    //`left_last` is the maximum of the left tree and `right_last` the minimum of the right tree. At the end the
+    //two trees become the children of the last node visited, which is returned as the new root.
+    fn splay(&mut self, mut t: usize, key: u32) -> usize {
+        let nodes = &mut self.tree.nodes;
+        let (mut left_root, mut left_last): (Option<usize>, Option<usize>) = (None, None);
+        let (mut right_root, mut right_last): (Option<usize>, Option<usize>) = (None, None);
+
+        loop {
+            if key < nodes[t].key {
+                let Some(mut child) = nodes[t].id_left else {
+                    break;
+                };
+                if key < nodes[child].key {
+                    //Zig-zig: rotate right.
+                    nodes[t].id_left = nodes[child].id_right;
+                    nodes[child].id_right = Some(t);
+                    t = child;
+                    match nodes[t].id_left {
+                        Some(next) => child = next,
+                        None => break,
+                    }
+                }
+                //Link t to the right tree.
+                match right_last {
+                    Some(r) => nodes[r].id_left = Some(t),
+                    None => right_root = Some(t),
+                }
+                right_last = Some(t);
+                t = child;
+            } else if key > nodes[t].key {
+                let Some(mut child) = nodes[t].id_right else {
+                    break;
+                };
+                if key > nodes[child].key {
+                    //Zag-zag: rotate left.
+                    nodes[t].id_right = nodes[child].id_left;
+                    nodes[child].id_left = Some(t);
+                    t = child;
+                    match nodes[t].id_right {
+                        Some(next) => child = next,
+                        None => break,
+                    }
+                }
+                //Link t to the left tree.
+                match left_last {
+                    Some(l) => nodes[l].id_right = Some(t),
+                    None => left_root = Some(t),
+                }
+                left_last = Some(t);
+                t = child;
+            } else {
+                break;
+            }
+        }
+
+        //Assemble: the subtrees of t are attached to the left and right trees, which become the children of t.
+        match left_last {
+            Some(l) => nodes[l].id_right = nodes[t].id_left,
+            None => left_root = nodes[t].id_left,
+        }
+        match right_last {
+            Some(r) => nodes[r].id_left = nodes[t].id_right,
+            None => right_root = nodes[t].id_right,
+        }
+        nodes[t].id_left = left_root;
+        nodes[t].id_right = right_root;
+        t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::rng::Rng;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_insert_and_contains() {
+        for splay_on_lookup in [false, true] {
+            let mut rng = Rng::new(29);
+            let mut splay = SplayTree::new(splay_on_lookup);
+            let mut root = None;
+            let mut expected = BTreeSet::new();
+
+            for _ in 0..2000 {
+                let key = rng.next_u32() % 500;
+                assert_eq!(splay.insert(&mut root, key), expected.insert(key));
+                assert!(splay.is_bst(root));
+            }
+            assert_eq!(
+                splay.keys(root),
+                expected.iter().copied().collect::<Vec<_>>()
+            );
+
+            for key in 0..600 {
+                let old_root = root;
+                assert_eq!(splay.contains(&mut root, key), expected.contains(&key));
+                if splay_on_lookup {
+                    //The last node visited is now the root.
+                    if expected.contains(&key) {
+                        assert_eq!(splay.tree.nodes[root.unwrap()].key, key);
+                    }
+                } else {
+                    assert_eq!(root, old_root);
+                }
+            }
+            assert!(splay.is_bst(root));
+        }
+    }
+
+    #[test]
+    fn test_split_and_merge() {
+        let mut rng = Rng::new(7);
+        let mut splay = SplayTree::new(true);
+        let mut root = None;
+        for _ in 0..1000 {
+            splay.insert(&mut root, rng.next_u32() % 10_000);
+        }
+        let all = splay.keys(root);
+
+        for key in [0, 1, 5000, 9999, 10_000, u32::MAX] {
+            let (left, right) = splay.split(root, key);
+            assert!(splay.is_bst(left));
+            assert!(splay.is_bst(right));
+            let left_keys = splay.keys(left);
+            let right_keys = splay.keys(right);
+            assert!(left_keys.iter().all(|&k| k < key));
+            assert!(right_keys.iter().all(|&k| k >= key));
+            assert_eq!(left_keys.len() + right_keys.len(), all.len());
+
+            root = splay.merge(left, right);
+            assert!(splay.is_bst(root));
+            assert_eq!(splay.keys(root), all);
+        }
+
+        //Empty sets.
+        assert_eq!(splay.split(None, 10), (None, None));
+        assert_eq!(splay.merge(None, None), None);
+        assert_eq!(splay.merge(None, root), root);
+    }
+
+    #[test]
+    fn test_sequential_access_is_amortised() {
+        //Increasing inserts build a path, then accessing the keys in order flattens it again.
+        //Sequential access theorem: the n accesses visit O(n) nodes in total, a plain BST search on the path
+        //would visit about n² / 2 of them.
+        let n = 10_000;
+        let mut splay = SplayTree::new(true);
+        let mut root = None;
+        for key in 0..n {
+            splay.insert(&mut root, key);
+        }
+        assert_eq!(depth(&splay, root, 0), n as usize - 1);
+
+        let mut visited = 0;
+        for key in 0..n {
+            visited += depth(&splay, root, key) + 1;
+            assert!(splay.contains(&mut root, key));
+            assert_eq!(splay.tree.nodes[root.unwrap()].key, key);
+        }
+        assert!(visited <= 10 * n as usize, "{} nodes visited", visited);
+        assert_eq!(splay.keys(root), (0..n).collect::<Vec<_>>());
+    }
+
+    //Number of edges from root to the node holding key, which must be in the set.
+    fn depth(splay: &SplayTree, root: Option<usize>, key: u32) -> usize {
+        let mut id = root.unwrap();
+        let mut depth = 0;
+        while splay.tree.nodes[id].key != key {
+            let node = &splay.tree.nodes[id];
+            id = if key < node.key { node.id_left } else { node.id_right }.unwrap();
+            depth += 1;
+        }
+        depth
+    }
+}
@@ -0,0 +1,214 @@
+use super::{Node, Tree};
+use crate::rng::Rng;
+
+/// Treap stored in the same arena of `Tree`.
+/// Every node gets a random priority: the keys are ordered as in a BST and the priorities as in a max heap,
+/// so the shape of the tree is the one of a BST built inserting the keys by decreasing priority and the
+/// expected height is O(log n).
+///
+/// As for `SplayTree` the arena can hold several ordered sets, each one identified by its root.
+#[derive(Debug)]
+pub struct Treap {
+    tree: Tree,
+    priorities: Vec<u32>,
+    rng: Rng,
+}
+
+impl Treap {
+    /// Creates an empty arena, the priorities are generated from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            tree: Tree { nodes: Vec::new() },
+            priorities: Vec::new(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Inserts `key` in the set rooted at `root` and updates `root`.
+    /// Returns `false` if the key was already present.
+    pub fn insert(&mut self, root: &mut Option<usize>, key: u32) -> bool {
+        if self.contains(*root, key) {
+            return false;
+        }
+
+        self.tree.nodes.push(Node::new(key));
+        self.priorities.push(self.rng.next_u32());
+        let new_id = Some(self.tree.nodes.len() - 1);
+
+        let (left, right) = self.split(*root, key);
+        let left = self.merge(left, new_id);
+        *root = self.merge(left, right);
+        true
+    }
+
+    /// Returns `true` if `key` is in the set rooted at `root`.
+    pub fn contains(&self, root: Option<usize>, key: u32) -> bool {
+        let mut node = root;
+        while let Some(id) = node {
+            let current = &self.tree.nodes[id];
+            if key == current.key {
+                return true;
+            }
+            node = if key < current.key {
+                current.id_left
+            } else {
+                current.id_right
+            };
+        }
+        false
+    }
+
+    /// Splits the set rooted at `root` in the keys smaller than `key` and the keys greater or equal to `key`.
+    /// Returns the roots of the two sets.
+    pub fn split(&mut self, root: Option<usize>, key: u32) -> (Option<usize>, Option<usize>) {
+        let Some(id) = root else {
+            return (None, None);
+        };
+
+        if self.tree.nodes[id].key < key {
+            let (left, right) = self.split(self.tree.nodes[id].id_right, key);
+            self.tree.nodes[id].id_right = left;
+            (Some(id), right)
+        } else {
+            let (left, right) = self.split(self.tree.nodes[id].id_left, key);
+            self.tree.nodes[id].id_left = right;
+            (left, Some(id))
+        }
+    }
+
+    /// Joins two sets and returns the root of the result.
+    /// Every key of the set rooted at `left` must be smaller than every key of the set rooted at `right`.
+    pub fn merge(&mut self, left: Option<usize>, right: Option<usize>) -> Option<usize> {
+        let (Some(l), Some(r)) = (left, right) else {
+            return left.or(right);
+        };
+
+        //The root with the highest priority stays on top.
+        if self.priorities[l] >= self.priorities[r] {
+            self.tree.nodes[l].id_right = self.merge(self.tree.nodes[l].id_right, right);
+            Some(l)
+        } else {
+            self.tree.nodes[r].id_left = self.merge(left, self.tree.nodes[r].id_left);
+            Some(r)
+        }
+    }
+
+    /// Returns the keys of the set rooted at `root` in order.
+    pub fn keys(&self, root: Option<usize>) -> Vec<u32> {
+        let mut keys = Vec::new();
+        self.keys_inorder(root, &mut keys);
+        keys
+    }
+
+    fn keys_inorder(&self, root: Option<usize>, keys: &mut Vec<u32>) {
+        if let Some(id) = root {
+            self.keys_inorder(self.tree.nodes[id].id_left, keys);
+            keys.push(self.tree.nodes[id].key);
+            self.keys_inorder(self.tree.nodes[id].id_right, keys);
+        }
+    }
+
+    pub fn is_bst(&self, root: Option<usize>) -> bool {
+        self.tree.is_bst(root)
+    }
+
+    /// Checks the max heap order of the priorities, like `is_heap_util` does for the keys.
+    /// A treap is not a complete tree, so every parent is compared with all its children.
+    pub fn is_heap_ordered(&self, root: Option<usize>) -> bool {
+        if let Some(id) = root {
+            let node = &self.tree.nodes[id];
+            for child in [node.id_left, node.id_right].into_iter().flatten() {
+                if self.priorities[id] < self.priorities[child] {
+                    return false;
+                }
+            }
+            return self.is_heap_ordered(node.id_left) && self.is_heap_ordered(node.id_right);
+        }
+        true
+    }
+
+    /// Returns the number of nodes on the longest path from `root` to a leaf.
+    pub fn height(&self, root: Option<usize>) -> usize {
+        match root {
+            Some(id) => {
+                let node = &self.tree.nodes[id];
+                self.height(node.id_left).max(self.height(node.id_right)) + 1
+            }
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_insert_keeps_invariants() {
+        let mut rng = Rng::new(2);
+        let mut treap = Treap::new(29);
+        let mut root = None;
+        let mut expected = BTreeSet::new();
+
+        for _ in 0..3000 {
+            let key = rng.next_u32() % 1000;
+            assert_eq!(treap.insert(&mut root, key), expected.insert(key));
+        }
+        assert!(treap.is_bst(root));
+        assert!(treap.is_heap_ordered(root));
+        assert_eq!(
+            treap.keys(root),
+            expected.iter().copied().collect::<Vec<_>>()
+        );
+        for key in 0..1100 {
+            assert_eq!(treap.contains(root, key), expected.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_sorted_inserts_stay_shallow() {
+        let n = 1 << 14;
+        let mut treap = Treap::new(1);
+        let mut root = None;
+        for key in 0..n {
+            treap.insert(&mut root, key);
+        }
+        assert!(treap.is_bst(root));
+        assert!(treap.is_heap_ordered(root));
+        //A BST built with sorted inserts would have height n.
+        assert!(treap.height(root) < 60);
+    }
+
+    #[test]
+    fn test_split_and_merge() {
+        let mut treap = Treap::new(5);
+        let mut root = None;
+        for key in (0..2000).step_by(3) {
+            treap.insert(&mut root, key);
+        }
+        let all = treap.keys(root);
+
+        for key in [0, 1, 999, 1000, 1998, 5000] {
+            let (left, right) = treap.split(root, key);
+            for part in [left, right] {
+                assert!(treap.is_bst(part));
+                assert!(treap.is_heap_ordered(part));
+            }
+            assert!(treap.keys(left).iter().all(|&k| k < key));
+            assert!(treap.keys(right).iter().all(|&k| k >= key));
+
+            root = treap.merge(left, right);
+            assert!(treap.is_heap_ordered(root));
+            assert_eq!(treap.keys(root), all);
+        }
+
+        //Breaking the priorities is detected.
+        let root_id = root.unwrap();
+        let child = treap.tree.nodes[root_id].id_left.unwrap();
+        treap.priorities[root_id] = 0;
+        treap.priorities[child] = 1;
+        assert!(!treap.is_heap_ordered(root));
+    }
+}
@@ -0,0 +1,76 @@
+//Runs the same random sequence of chmin updates and max queries on the iterative tree of this crate
+//and on the recursive one of the segment_tree crate, and prints the operations per second of both.
+//cargo bench --bench throughput [-- <size> <operations>]
+
+use segment_tree::rng::Rng;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+enum Op {
+    Update(usize, usize, i32),
+    Query(usize, usize),
+}
+
+fn random_ops(size: usize, count: usize, rng: &mut Rng) -> Vec<Op> {
+    (0..count)
+        .map(|_| {
+            let a = rng.below(size);
+            let b = rng.below(size);
+            let (start, end) = (a.min(b), a.max(b) + 1);
+            if rng.below(2) == 0 {
+                Op::Update(start, end, rng.below(1 << 30) as i32)
+            } else {
+                Op::Query(start, end)
+            }
+        })
+        .collect()
+}
+
+fn report(name: &str, ops: usize, elapsed: Duration) {
+    println!(
+        "{:<12} {:>10.3} ms {:>14.0} ops/s",
+        name,
+        elapsed.as_secs_f64() * 1e3,
+        ops as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    //cargo bench passes --bench to the binary, only the numeric arguments are ours.
+    let args: Vec<usize> = std::env::args()
+        .skip(1)
+        .filter_map(|arg| arg.parse().ok())
+        .collect();
+    let size = args.first().copied().unwrap_or(1 << 20);
+    let count = args.get(1).copied().unwrap_or(1 << 21);
+
+    let mut rng = Rng::new(2324);
+    let array: Vec<i32> = (0..size).map(|_| rng.below(1 << 30) as i32).collect();
+    let ops = random_ops(size, count, &mut rng);
+    println!("size {} operations {}", size, count);
+
+    let mut iterative = handson_2::SegmentTree::new(&array);
+    let start = Instant::now();
+    let mut checksum_iterative = 0i64;
+    for op in &ops {
+        match *op {
+            Op::Update(l, r, t) => iterative.update(l..r, t),
+            Op::Query(l, r) => checksum_iterative += iterative.query_max(l..r).unwrap() as i64,
+        }
+    }
+    report("iterative", count, start.elapsed());
+
+    let mut recursive = segment_tree::SegmentTree::new(&array);
+    let start = Instant::now();
+    let mut checksum_recursive = 0i64;
+    for op in &ops {
+        match *op {
+            Op::Update(l, r, t) => recursive.update(l..r, t),
+            Op::Query(l, r) => checksum_recursive += recursive.max_query(l..r).unwrap() as i64,
+        }
+    }
+    report("recursive", count, start.elapsed());
+
+    //The two trees must agree, otherwise the comparison means nothing.
+    assert_eq!(black_box(checksum_iterative), black_box(checksum_recursive));
+}
@@ -0,0 +1,290 @@
+use segment_tree::range::{to_half_open, RangeError};
+use std::ops::RangeBounds;
+
+//Bottom-up segment tree in the 2n layout: the leaves are tree[n..2n], the parent of v is v / 2 and the root is 1.
+//Range max queries and range chmin updates (a[i] = min(a[i], t)), without recursion.
+//tree[v] is always the max of its subtree with every tag of v and below applied,
+//lazy[v] is the chmin still to be pushed to the children of v, if any.
+#[derive(Debug)]
+pub struct SegmentTree {
+    size: usize,
+    height: u32,
+    tree: Vec<i32>,
+    lazy: Vec<Option<i32>>,
+}
+
+impl SegmentTree {
+    pub fn new(array: &[i32]) -> Self {
+        let mut segment_tree = SegmentTree {
+            size: 0,
+            height: 0,
+            tree: Vec::new(),
+            lazy: Vec::new(),
+        };
+        segment_tree.rebuild_from(array);
+        segment_tree
+    }
+
+    //Replaces the array with a new one of any length, reusing the memory of the tree. O(n).
+    pub fn rebuild_from(&mut self, array: &[i32]) {
+        let size = array.len();
+        self.size = size;
+        //Number of levels above the leaves, the leaf size + i has at most this many ancestors.
+        self.height = usize::BITS - size.leading_zeros();
+        //tree[0] is never used.
+        self.tree.clear();
+        self.tree.resize(2 * size, 0);
+        self.tree[size..].copy_from_slice(array);
+        for i in (1..size).rev() {
+            self.tree[i] = self.tree[i * 2].max(self.tree[i * 2 + 1]);
+        }
+        self.lazy.clear();
+        self.lazy.resize(size, None);
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn print_tree(&self) {
+        println!("{:?}", self.tree);
+    }
+
+    //The current array, with every pending chmin applied. O(n).
+    pub fn to_vec(&mut self) -> Vec<i32> {
+        self.leaves().collect()
+    }
+
+    //The current values in order. The tags are all pushed first, in O(n), so the leaves are up to date.
+    pub fn leaves(&mut self) -> impl ExactSizeIterator<Item = i32> + '_ {
+        self.push_all();
+        self.tree[self.size..].iter().copied()
+    }
+
+    //The parent of v is v / 2 < v, so going up the indices every tag is pushed before the ones of its children.
+    fn push_all(&mut self) {
+        for v in 1..self.size {
+            if let Some(t) = self.lazy[v].take() {
+                self.apply(2 * v, t);
+                self.apply(2 * v + 1, t);
+            }
+        }
+    }
+
+    //Applies the chmin to the node and, if it is not a leaf, stores it as a tag for its children.
+    fn apply(&mut self, v: usize, t: i32) {
+        self.tree[v] = self.tree[v].min(t);
+        if v < self.size {
+            self.lazy[v] = Some(self.lazy[v].map_or(t, |tag| tag.min(t)));
+        }
+    }
+
+    //Pushes down the tags of every ancestor of the leaf v, from the root to the leaf.
+    fn push(&mut self, v: usize) {
+        for s in (1..=self.height).rev() {
+            let i = v >> s;
+            if i == 0 {
+                continue;
+            }
+            if let Some(t) = self.lazy[i].take() {
+                self.apply(2 * i, t);
+                self.apply(2 * i + 1, t);
+            }
+        }
+    }
+
+    //Recomputes the ancestors of v from the bottom, keeping the tags they still hold.
+    fn rebuild(&mut self, mut v: usize) {
+        while v > 1 {
+            v /= 2;
+            let max = self.tree[2 * v].max(self.tree[2 * v + 1]);
+            self.tree[v] = self.lazy[v].map_or(max, |t| max.min(t));
+        }
+    }
+
+    //Max in the range, positions are 0 indexed, None if the range is empty. Panics if the range is reversed or out of bounds.
+    pub fn query_max(&self, range: impl RangeBounds<usize>) -> Option<i32> {
+        self.try_query_max(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    //The half-open range [left, right) goes up from the leaves and at every level the nodes on the borders that are
+    //not covered by their parent are taken. The query does not push: the parent of a node taken on the left is
+    //always the ancestor of the first leaf one level up, so every node taken so far on the left is below
+    //first >> s and its tag caps max_left (the same for the right with the last leaf).
+    pub fn try_query_max(&self, range: impl RangeBounds<usize>) -> Result<Option<i32>, RangeError> {
+        let range = to_half_open(range, self.size)?;
+        if range.is_empty() {
+            return Ok(None);
+        }
+        let first = range.start + self.size;
+        let last = range.end - 1 + self.size;
+        let cap = |max: i32, v: usize| self.lazy[v].map_or(max, |t| max.min(t));
+
+        let mut left = first;
+        let mut right = last + 1;
+        let mut max_left = i32::MIN;
+        let mut max_right = i32::MIN;
+        for s in 1..=self.height {
+            if left < right {
+                if left % 2 == 1 {
+                    max_left = max_left.max(self.tree[left]);
+                    left += 1;
+                }
+                if right % 2 == 1 {
+                    right -= 1;
+                    max_right = max_right.max(self.tree[right]);
+                }
+                left /= 2;
+                right /= 2;
+            }
+            //lazy[0] is never set, so going past the root changes nothing.
+            max_left = cap(max_left, first >> s);
+            max_right = cap(max_right, last >> s);
+        }
+
+        Ok(Some(max_left.max(max_right)))
+    }
+
+    //Sets a[i] = min(a[i], t) for every i in the range, an empty range changes nothing.
+    //Panics if the range is reversed or out of bounds.
+    pub fn update(&mut self, range: impl RangeBounds<usize>, t: i32) {
+        self.try_update(range, t)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    //Same walk as the query: the tags above the borders are pushed, the chmin is applied to the nodes that cover the range
+    //and then the ancestors of the two borders are rebuilt.
+    pub fn try_update(&mut self, range: impl RangeBounds<usize>, t: i32) -> Result<(), RangeError> {
+        let range = to_half_open(range, self.size)?;
+        if range.is_empty() {
+            return Ok(());
+        }
+        let first = range.start + self.size;
+        let last = range.end - 1 + self.size;
+        self.push(first);
+        self.push(last);
+
+        let mut left = first;
+        let mut right = last + 1;
+        while left < right {
+            if left % 2 == 1 {
+                self.apply(left, t);
+                left += 1;
+            }
+            if right % 2 == 1 {
+                right -= 1;
+                self.apply(right, t);
+            }
+            left /= 2;
+            right /= 2;
+        }
+
+        self.rebuild(first);
+        self.rebuild(last);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use segment_tree::rng::Rng;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = Rng::new(34);
+        for size in 1..40 {
+            let mut naive: Vec<i32> = (0..size).map(|_| rng.below(100) as i32).collect();
+            let mut tree = SegmentTree::new(&naive);
+
+            for _ in 0..200 {
+                let a = rng.below(size);
+                let b = rng.below(size);
+                let range = a.min(b)..a.max(b) + 1;
+                if rng.below(2) == 0 {
+                    let t = rng.below(100) as i32;
+                    tree.update(range.clone(), t);
+                    for x in &mut naive[range] {
+                        *x = (*x).min(t);
+                    }
+                } else {
+                    let expected = naive[range.clone()].iter().max().copied();
+                    assert_eq!(tree.query_max(range), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut tree = SegmentTree::new(&[5, 1, 4]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert!(tree.try_update(reversed, 0).is_err());
+        assert!(tree.try_update(0..4, 0).is_err());
+        assert!(tree.try_query_max(4..).is_err());
+        assert_eq!(tree.query_max(..), Some(5));
+    }
+
+    #[test]
+    fn test_to_vec_and_rebuild() {
+        let mut rng = Rng::new(46);
+        for size in 1..40 {
+            let mut naive: Vec<i32> = (0..size).map(|_| rng.below(100) as i32).collect();
+            let mut tree = SegmentTree::new(&naive);
+            for _ in 0..20 {
+                let a = rng.below(size);
+                let b = rng.below(size);
+                let t = rng.below(100) as i32;
+                tree.update(a.min(b)..=a.max(b), t);
+                naive[a.min(b)..=a.max(b)]
+                    .iter_mut()
+                    .for_each(|x| *x = (*x).min(t));
+            }
+            assert_eq!(tree.to_vec(), naive);
+            //The tags have been pushed, the queries still agree.
+            assert_eq!(tree.query_max(..), naive.iter().max().copied());
+        }
+
+        let mut tree = SegmentTree::new(&[5, 1, 4]);
+        tree.update(.., 3);
+        tree.rebuild_from(&[2, 9]);
+        assert_eq!(tree.to_vec(), [2, 9]);
+        assert_eq!(tree.query_max(1..), Some(9));
+    }
+
+    //The query reads the tags of the ancestors without pushing them.
+    #[test]
+    fn test_query_does_not_push() {
+        let mut tree = SegmentTree::new(&[9, 10, 11, 6, 5, 10, 11]);
+        tree.update(.., 8);
+        tree.update(4..6, 3);
+        let lazy = tree.lazy.clone();
+        let tree = &tree;
+        assert_eq!(tree.query_max(..), Some(8));
+        assert_eq!(tree.query_max(4..6), Some(3));
+        assert_eq!(tree.query_max(3..=4), Some(6));
+        assert_eq!(tree.query_max(5..), Some(8));
+        assert_eq!(tree.lazy, lazy);
+    }
+
+    #[test]
+    fn test_full_domain() {
+        let mut tree = SegmentTree::new(&[i32::MIN, i32::MAX, 7]);
+        tree.update(1..1, 0);
+        assert_eq!(tree.query_max(3..), None);
+        assert_eq!(tree.query_max(..1), Some(i32::MIN));
+        //A chmin with i32::MAX is a real tag, it changes nothing and must not hide the tags below it.
+        tree.update(1..=2, 5);
+        tree.update(.., i32::MAX);
+        assert_eq!(tree.query_max(..), Some(5));
+        tree.update(1.., i32::MIN);
+        assert_eq!(tree.query_max(1..=1), Some(i32::MIN));
+        assert_eq!(tree.query_max(..), Some(i32::MIN));
+    }
+}
@@ -0,0 +1,84 @@
+//Read-heavy workload on the B-ary WideSegmentTree and on SegmentTree: random range max queries with one short chmin
+//every 100 operations. Prints the build time and the operations per second of both.
+//cargo bench --bench wide [-- <size> <operations>]
+
+use segment_2::wide::WideSegmentTree;
+use segment_2::SegmentTree;
+use segment_tree::rng::Rng;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+enum Op {
+    Update(usize, usize, i64),
+    Query(usize, usize),
+}
+
+fn random_ops(size: usize, count: usize, rng: &mut Rng) -> Vec<Op> {
+    (0..count)
+        .map(|_| {
+            let a = rng.below(size);
+            if rng.below(100) == 0 {
+                let end = (a + 1 + rng.below(64)).min(size);
+                Op::Update(a, end, rng.below(1 << 30) as i64)
+            } else {
+                let b = rng.below(size);
+                Op::Query(a.min(b), a.max(b) + 1)
+            }
+        })
+        .collect()
+}
+
+fn report(name: &str, build: Duration, ops: usize, elapsed: Duration) {
+    println!(
+        "{:<8} build {:>9.3} ms {:>14.0} ops/s",
+        name,
+        build.as_secs_f64() * 1e3,
+        ops as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    //cargo bench passes --bench to the binary, only the numeric arguments are ours.
+    let args: Vec<usize> = std::env::args()
+        .skip(1)
+        .filter_map(|arg| arg.parse().ok())
+        .collect();
+    let size = args.first().copied().unwrap_or(10_000_000);
+    let count = args.get(1).copied().unwrap_or(1_000_000);
+
+    let mut rng = Rng::new(44);
+    let array: Vec<i64> = (0..size).map(|_| rng.below(1 << 30) as i64).collect();
+    let ops = random_ops(size, count, &mut rng);
+    println!("size {} operations {}", size, count);
+
+    let start = Instant::now();
+    let mut wide = WideSegmentTree::new(&array);
+    let build = start.elapsed();
+    let start = Instant::now();
+    let mut checksum_wide = 0i64;
+    for op in &ops {
+        match *op {
+            Op::Update(l, r, t) => wide.update_range_with_value(l..r, t),
+            Op::Query(l, r) => checksum_wide += wide.query_range(l..r).unwrap(),
+        }
+    }
+    report("wide", build, count, start.elapsed());
+    drop(wide);
+
+    let start = Instant::now();
+    let mut binary = SegmentTree::new(size);
+    binary.build(&array);
+    let build = start.elapsed();
+    let start = Instant::now();
+    let mut checksum_binary = 0i64;
+    for op in &ops {
+        match *op {
+            Op::Update(l, r, t) => binary.update_range_with_value(l..r, t),
+            Op::Query(l, r) => checksum_binary += binary.query_range(l..r).unwrap(),
+        }
+    }
+    report("binary", build, count, start.elapsed());
+
+    //The two trees must agree, otherwise the comparison means nothing.
+    assert_eq!(black_box(checksum_wide), black_box(checksum_binary));
+}
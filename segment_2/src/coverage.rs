@@ -0,0 +1,232 @@
+use segment_tree::lazy::LazySegmentTree;
+use segment_tree::ops::{Add, MinMax};
+use segment_tree::range::{to_half_open, RangeError};
+use std::collections::HashMap;
+use std::ops::{Range, RangeBounds};
+
+//Online version of sweep + is_there: segments over the points 0..len can be added and removed between the queries.
+//Every point holds the number of segments that cover it, a segment is a range +1 (or -1 to remove it) with lazy add,
+//and the nodes keep both min and max of the coverage.
+#[derive(Debug)]
+pub struct CoverageIndex {
+    tree: LazySegmentTree<MinMax<i64>, Add<i64>>,
+    //How many times each segment has been added, to refuse removing a segment that is not there.
+    segments: HashMap<Range<usize>, usize>,
+    segment_count: usize,
+}
+
+impl CoverageIndex {
+    //Index over the points 0..len, with no segment.
+    pub fn new(len: usize) -> Self {
+        CoverageIndex {
+            tree: LazySegmentTree::new(&vec![(0, 0); len]),
+            segments: HashMap::new(),
+            segment_count: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    //Number of segments currently in the index.
+    pub fn segment_count(&self) -> usize {
+        self.segment_count
+    }
+
+    //Adds the segment covering the range, O(log n). An empty segment covers no point.
+    //Panics if the range is reversed or out of bounds.
+    pub fn add_segment(&mut self, range: impl RangeBounds<usize>) {
+        self.try_add_segment(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_add_segment(&mut self, range: impl RangeBounds<usize>) -> Result<(), RangeError> {
+        let range = to_half_open(range, self.len())?;
+        self.tree.update(range.clone(), Add(1));
+        *self.segments.entry(range).or_insert(0) += 1;
+        self.segment_count += 1;
+        Ok(())
+    }
+
+    //Removes one copy of the segment covering the range, O(log n).
+    //Returns false, leaving the index untouched, if the segment was never added.
+    //Panics if the range is reversed or out of bounds.
+    pub fn remove_segment(&mut self, range: impl RangeBounds<usize>) -> bool {
+        self.try_remove_segment(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_remove_segment(
+        &mut self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<bool, RangeError> {
+        let range = to_half_open(range, self.len())?;
+        let Some(count) = self.segments.get_mut(&range) else {
+            return Ok(false);
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.segments.remove(&range);
+        }
+        self.segment_count -= 1;
+        self.tree.update(range, Add(-1));
+        Ok(true)
+    }
+
+    //Highest coverage of a point in the range, O(log n), None if the range is empty.
+    //Panics if the range is reversed or out of bounds.
+    pub fn max_coverage(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.try_max_coverage(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_max_coverage(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i64>, RangeError> {
+        let range = to_half_open(range, self.len())?;
+        Ok((!range.is_empty()).then(|| self.tree.query(range).1))
+    }
+
+    //Lowest coverage of a point in the range, O(log n), None if the range is empty.
+    //Panics if the range is reversed or out of bounds.
+    pub fn min_coverage(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.try_min_coverage(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_min_coverage(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i64>, RangeError> {
+        let range = to_half_open(range, self.len())?;
+        Ok((!range.is_empty()).then(|| self.tree.query(range).0))
+    }
+
+    //Returns 1 if a point in the range is covered by exactly k segments, 0 otherwise.
+    //The search skips the subtrees whose [min, max] does not contain k, so it is not O(log n) like the other operations:
+    //a subtree it enters without finding k holds two adjacent points whose coverages jump over k, and the cost is
+    //O((1 + c) log n) with c such jumps before the answer. When every segment ends where another begins the coverage
+    //moves by 1 and c = 0, but it can be O(n): adding i..=i twice for every even i and asking for k = 1 visits the
+    //whole tree. Panics if the range is reversed or out of bounds.
+    pub fn is_there(&self, range: impl RangeBounds<usize>, k: i64) -> i32 {
+        self.try_is_there(range, k)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_is_there(&self, range: impl RangeBounds<usize>, k: i64) -> Result<i32, RangeError> {
+        let position = self.tree.try_find_first(
+            range,
+            |&(min, max)| min <= k && k <= max,
+            |&(coverage, _)| coverage == k,
+        )?;
+        Ok(if position.is_some() { 1 } else { 0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use segment_tree::rng::Rng;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = Rng::new(37);
+        for len in 1..25 {
+            let mut index = CoverageIndex::new(len);
+            let mut naive = vec![0i64; len];
+            let mut added: Vec<Range<usize>> = Vec::new();
+
+            for _ in 0..300 {
+                let a = rng.below(len);
+                let b = rng.below(len);
+                let range = a.min(b)..a.max(b) + 1;
+                match rng.below(4) {
+                    0 => {
+                        index.add_segment(range.clone());
+                        naive[range.clone()].iter_mut().for_each(|x| *x += 1);
+                        added.push(range);
+                    }
+                    1 if !added.is_empty() => {
+                        let removed = added.swap_remove(rng.below(added.len()));
+                        assert!(index.remove_segment(removed.clone()));
+                        naive[removed].iter_mut().for_each(|x| *x -= 1);
+                    }
+                    _ => {
+                        let k = rng.below(4) as i64;
+                        let points = &naive[range.clone()];
+                        assert_eq!(index.is_there(range.clone(), k), points.contains(&k) as i32);
+                        assert_eq!(
+                            index.max_coverage(range.clone()),
+                            points.iter().max().copied()
+                        );
+                        assert_eq!(index.min_coverage(range), points.iter().min().copied());
+                    }
+                }
+                assert_eq!(index.segment_count(), added.len());
+            }
+        }
+    }
+
+    //The worst case of is_there: the coverage alternates 2, 0, 2, 0, ... and no point holds 1.
+    #[test]
+    fn test_is_there_alternating() {
+        let len = 1 << 12;
+        let mut index = CoverageIndex::new(len);
+        for i in (0..len).step_by(2) {
+            index.add_segment(i..=i);
+            index.add_segment(i..=i);
+        }
+        assert_eq!(index.is_there(.., 1), 0);
+        assert_eq!(index.is_there(.., 2), 1);
+        assert_eq!(index.is_there(1..2, 0), 1);
+        index.remove_segment(len - 2..=len - 2);
+        assert_eq!(index.is_there(.., 1), 1);
+        assert_eq!(index.is_there(..len - 2, 1), 0);
+    }
+
+    #[test]
+    fn test_remove_missing_segment() {
+        let mut index = CoverageIndex::new(10);
+        index.add_segment(2..=5);
+        index.add_segment(2..=5);
+        assert!(!index.remove_segment(2..5));
+        assert_eq!(index.max_coverage(..), Some(2));
+        assert!(index.remove_segment(2..=5));
+        assert!(index.remove_segment(2..6));
+        assert!(!index.remove_segment(2..6));
+        assert_eq!(index.max_coverage(..), Some(0));
+        assert_eq!(index.is_there(.., 0), 1);
+        assert_eq!(index.is_there(.., 1), 0);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut index = CoverageIndex::new(4);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert!(index.try_add_segment(reversed).is_err());
+        assert!(index.try_remove_segment(0..5).is_err());
+        assert!(index.try_is_there(5.., 0).is_err());
+        assert!(index.try_max_coverage(0..=4).is_err());
+        assert_eq!(index.segment_count(), 0);
+    }
+
+    #[test]
+    fn test_empty_segments() {
+        let mut index = CoverageIndex::new(4);
+        index.add_segment(1..1);
+        assert_eq!(index.segment_count(), 1);
+        assert_eq!(index.max_coverage(..), Some(0));
+        assert_eq!(index.max_coverage(4..), None);
+        assert_eq!(index.min_coverage(2..2), None);
+        assert_eq!(index.is_there(2..2, 0), 0);
+        assert!(index.remove_segment(1..1));
+        assert_eq!(index.segment_count(), 0);
+    }
+}
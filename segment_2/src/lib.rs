@@ -1,166 +1,323 @@
+pub mod coverage;
+pub mod persistent;
+pub mod sparse;
+pub mod wide;
+
+pub use segment_tree::lazy::Checkpoint;
+use segment_tree::lazy::LazySegmentTree;
+use segment_tree::ops::{ChMin, MaxCount, MaxInfo, MinMax};
+use segment_tree::range::to_half_open;
+use segment_tree::range::RangeError;
+use std::ops::RangeBounds;
+
 //This struct is used for bothe the assignements.
+//It is the chmin instantiation of the generic LazySegmentTree over the pair of monoids MinMax and MaxCount.
+//With both min and max the exact searches can skip a subtree when the value is below its min or above its max,
+//MaxCount keeps how many positions hold the max and the second max for the argmax queries.
 pub struct SegmentTree {
-    tree: Vec<i64>,
-    lazy: Vec<i64>,
-    size: usize,
+    tree: LazySegmentTree<(MinMax<i64>, MaxCount<i64>), ChMin<i64>>,
+}
+
+type Value = ((i64, i64), MaxInfo<i64>);
+
+//Leaf value of the tree.
+fn leaf(value: i64) -> Value {
+    ((value, value), MaxInfo::leaf(value))
 }
 
 //To instantiate a new segment tree, call new with the array size first and then call the build funzion on the array.
 impl SegmentTree {
+    //The positions hold 0 until build is called.
     pub fn new(size: usize) -> Self {
-        let tree = vec![i64::MIN; size.next_power_of_two() * 2 - 1];
-        let lazy = vec![i64::MAX; size.next_power_of_two() * 2 - 1];
-        Self { tree, lazy, size }
+        let tree = LazySegmentTree::new(&vec![leaf(0); size]);
+        Self { tree }
     }
 
-    //Wrapper function to call the build routin with just one parameter.
+    //Builds the tree on the array, which must be long as the current size of the tree.
     pub fn build(&mut self, arr: &[i64]) {
-        self.build_recursive(arr, 0, 0, arr.len() - 1);
+        assert_eq!(
+            arr.len(),
+            self.tree.len(),
+            "Array size differs from the tree size"
+        );
+        self.rebuild_from(arr);
     }
 
-    fn build_recursive(&mut self, arr: &[i64], node: usize, start: usize, end: usize) {
-        if start == end {
-            self.tree[node] = arr[start];
-        } else {
-            let mid = (start + end) / 2;
-            self.build_recursive(arr, 2 * node + 1, start, mid);
-            self.build_recursive(arr, 2 * node + 2, mid + 1, end);
-            self.tree[node] = self.tree[2 * node + 1].max(self.tree[2 * node + 2]);
-        }
+    //Replaces the array with a new one of any length, reusing the memory of the tree. O(n).
+    pub fn rebuild_from(&mut self, arr: &[i64]) {
+        self.tree.rebuild_with(arr.len(), |i| leaf(arr[i]));
     }
 
-    //Wrapper function for query_recursive.
-    pub fn query_range(&mut self, left: usize, right: usize) -> i64 {
-        self.query_recursive(0, 0, self.size - 1, left - 1, right - 1)
+    pub fn len(&self) -> usize {
+        self.tree.len()
     }
 
-    fn query_recursive(
-        &mut self,
-        node: usize,
-        start: usize,
-        end: usize,
-        left: usize,
-        right: usize,
-    ) -> i64 {
-        self.propagate(node, start, end);
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
 
-        if right < start || left > end {
-            return i64::min_value(); // Out of range
-        }
+    //Appends a value, amortised O(log n). The size given to new is only the starting one: the storage doubles
+    //when it is full and keeps the pending chmins.
+    pub fn push(&mut self, value: i64) {
+        self.tree.push(leaf(value));
+    }
+
+    //Removes the last value and returns it, None if the tree is empty. O(log n).
+    pub fn pop(&mut self) -> Option<i64> {
+        self.tree.pop().map(|((value, _), _)| value)
+    }
+
+    //Removes the last values or appends copies of fill until there are len.
+    pub fn resize(&mut self, len: usize, fill: i64) {
+        self.tree.resize(len, leaf(fill));
+    }
+
+    //The current array, with every pending chmin applied. O(n).
+    pub fn to_vec(&self) -> Vec<i64> {
+        self.leaves().collect()
+    }
+
+    //The current values in order, without building the whole array.
+    pub fn leaves(&self) -> impl ExactSizeIterator<Item = i64> + '_ {
+        self.tree.leaves().map(|((value, _), _)| value)
+    }
+
+    //Max in the range, positions are 0 indexed. None if the range is empty, panics if it is reversed or out of bounds.
+    pub fn query_range(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.max_info(range).map(|info| info.max)
+    }
+
+    pub fn try_query_range(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i64>, RangeError> {
+        Ok(self.try_max_info(range)?.map(|info| info.max))
+    }
+
+    //query_range on every half-open range start..end of the batch, in order, on scoped threads sharing the tree.
+    //Panics if one of the ranges is reversed or out of bounds.
+    pub fn query_batch(&self, ranges: &[(usize, usize)]) -> Vec<Option<i64>> {
+        self.try_query_batch(ranges)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_query_batch(
+        &self,
+        ranges: &[(usize, usize)],
+    ) -> Result<Vec<Option<i64>>, RangeError> {
+        Ok(self
+            .tree
+            .try_query_batch(ranges)?
+            .into_iter()
+            .map(|(_, info)| (info.count > 0).then_some(info.max))
+            .collect())
+    }
+
+    //Max of the range with how many positions hold it and the second max, None if the range is empty.
+    pub fn max_info(&self, range: impl RangeBounds<usize>) -> Option<MaxInfo<i64>> {
+        self.try_max_info(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_max_info(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<MaxInfo<i64>>, RangeError> {
+        let info = self.tree.try_query(range)?.1;
+        Ok((info.count > 0).then_some(info))
+    }
+
+    //Leftmost position of the range holding the max, None if the range is empty. O(log n): every node inside
+    //the range whose max is the max of the range holds it, so the search goes down one path.
+    pub fn argmax(&self, range: impl RangeBounds<usize>) -> Option<usize> {
+        self.try_argmax(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        if left <= start && right >= end {
-            return self.tree[node];
+    pub fn try_argmax(&self, range: impl RangeBounds<usize>) -> Result<Option<usize>, RangeError> {
+        let range = to_half_open(range, self.tree.len())?;
+        match self.max_info(range.clone()) {
+            Some(info) => self.try_first_exact(range, info.max),
+            None => Ok(None),
         }
+    }
+
+    //Number of positions of the range holding the max, 0 if the range is empty. A chmin can make several maxima equal,
+    //the count stays right because the update goes down until the second max is below the new value.
+    pub fn max_count(&self, range: impl RangeBounds<usize>) -> usize {
+        self.tree.query(range).1.count
+    }
+
+    pub fn try_max_count(&self, range: impl RangeBounds<usize>) -> Result<usize, RangeError> {
+        Ok(self.tree.try_query(range)?.1.count)
+    }
 
-        let mid = (start + end) / 2;
-        let left_child = self.query_recursive(2 * node + 1, start, mid, left, right);
-        let right_child = self.query_recursive(2 * node + 2, mid + 1, end, left, right);
-        left_child.max(right_child)
+    //Largest value of the range strictly below the max, None if all the positions hold the max.
+    pub fn second_max(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.tree.query(range).1.second_max
     }
 
-    //Wrapepr function for update_range_recursive function.
-    pub fn update_range_with_value(&mut self, left: usize, right: usize, value: i64) {
-        self.update_range_recursive(0, 0, self.size - 1, left - 1, right - 1, value);
+    pub fn try_second_max(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i64>, RangeError> {
+        Ok(self.tree.try_query(range)?.1.second_max)
     }
 
-    fn update_range_recursive(
+    //Sets a[i] = min(a[i], value) for every i in the range.
+    pub fn update_range_with_value(&mut self, range: impl RangeBounds<usize>, value: i64) {
+        self.tree.update(range, ChMin(value));
+    }
+
+    pub fn try_update_range_with_value(
         &mut self,
-        node: usize,
-        start: usize,
-        end: usize,
-        left: usize,
-        right: usize,
+        range: impl RangeBounds<usize>,
         value: i64,
-    ) {
-        self.propagate(node, start, end);
-
-        if right < start || left > end {
-            return; // Out of range
-        }
+    ) -> Result<(), RangeError> {
+        self.tree.try_update(range, ChMin(value))
+    }
 
-        if left <= start && right >= end {
-            // Propagate changes.
-            self.tree[node] = self.tree[node].min(value);
-            if start != end {
-                self.lazy[2 * node + 1] = self.lazy[2 * node + 1].min(value);
-                self.lazy[2 * node + 2] = self.lazy[2 * node + 2].min(value);
-            }
-            return;
-        }
+    //Returns the current state, rollback goes back to it. From the first checkpoint on every update records the
+    //slots of the tree it overwrites, so it can be undone in time proportional to the nodes it touched.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.tree.checkpoint()
+    }
 
-        let mid = (start + end) / 2;
-        self.update_range_recursive(2 * node + 1, start, mid, left, right, value);
-        self.update_range_recursive(2 * node + 2, mid + 1, end, left, right, value);
+    //Undoes every update since the checkpoint. Going back to a checkpoint invalidates the ones taken after it.
+    //Panics if the checkpoint is no longer valid, or was taken before a build or rebuild_from.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.tree.rollback(checkpoint);
+    }
 
-        self.tree[node] = self.tree[2 * node + 1].max(self.tree[2 * node + 2]);
+    //Stops recording the updates and drops the history.
+    pub fn forget_history(&mut self) {
+        self.tree.forget_history();
     }
 
-    //The below function propagates changes using the current node and the lazy tree.
-    fn propagate(&mut self, node: usize, start: usize, end: usize) {
-        if self.lazy[node] != i64::MAX {
-            // Update the node and mark for lazy propagation
-            self.tree[node] = self.tree[node].min(self.lazy[node]);
-            if start != end {
-                self.lazy[2 * node + 1] = self.lazy[node].min(self.lazy[2 * node + 1]);
-                self.lazy[2 * node + 2] = self.lazy[node].min(self.lazy[2 * node + 2]);
-            }
-            self.lazy[node] = i64::MAX; // Reset lazy value
-        }
+    //Exact searches. They enter only the subtrees with min <= value <= max, so a subtree that is entered and holds no
+    //position equal to the value must contain two adjacent positions that jump over it (a[i] < value < a[i + 1] or the opposite).
+    //Each such pair is inside at most log n nodes, so with c jumps over the value in the range and m matching positions:
+    //  is_there, first_exact, last_exact: O((1 + c) log n), only the jumps met before the answer count;
+    //  count_exact: O((1 + c + m) log n).
+    //When adjacent values differ by at most 1 (for example a coverage where segments start and end one at a time) c = 0
+    //and the searches are O(log n); the worst case is values alternating value - 1 and value + 1, which is O(n).
+
+    //Returns 1 if there is a position in the range whose value is exactly `value`, 0 otherwise.
+    pub fn is_there(&self, range: impl RangeBounds<usize>, value: i64) -> i32 {
+        self.try_is_there(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
     }
 
-    //Wrapper function for query_vectors_recursive, that returns 1 or 0 instead of bool.
-    pub fn is_there(&mut self, left: usize, right: usize, value: i64) -> i32 {
-        if self.query_vectors_recursive(0, 0, self.size - 1, left, right, value) {
+    pub fn try_is_there(
+        &self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<i32, RangeError> {
+        Ok(if self.try_first_exact(range, value)?.is_some() {
             1
         } else {
             0
-        }
+        })
     }
 
-    fn query_vectors_recursive(
-        &mut self,
-        node: usize,
-        start: usize,
-        end: usize,
-        left: usize,
-        right: usize,
+    //First position in the range whose value is exactly `value`.
+    pub fn first_exact(&self, range: impl RangeBounds<usize>, value: i64) -> Option<usize> {
+        self.try_first_exact(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_first_exact(
+        &self,
+        range: impl RangeBounds<usize>,
         value: i64,
-    ) -> bool {
-        if right < start || left > end {
-            return false; // Out of range
-        }
+    ) -> Result<Option<usize>, RangeError> {
+        self.tree
+            .try_find_first(range, may_contain(value), is_exactly(value))
+    }
 
-        if left <= start && right >= end {
-            //Current node has the value we are searching so it is in the range, we are not going any further.
-            if self.tree[node] == value {
-                return true;
-            }
-            //The value we are searching is greater than the max of the range so the value cannot be in this range, we are not going any further then.
-            if self.tree[node] < value {
-                return false;
-            }
-        }
+    //Last position in the range whose value is exactly `value`.
+    pub fn last_exact(&self, range: impl RangeBounds<usize>, value: i64) -> Option<usize> {
+        self.try_last_exact(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        //We are in a leaf and we didn't return before so the value is not here for sure.
-        if start == end {
-            return false;
-        }
+    pub fn try_last_exact(
+        &self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<Option<usize>, RangeError> {
+        self.tree
+            .try_find_last(range, may_contain(value), is_exactly(value))
+    }
 
-        let mid = (start + end) / 2;
-        let left_child = self.query_vectors_recursive(2 * node + 1, start, mid, left, right, value);
-        let right_child =
-            self.query_vectors_recursive(2 * node + 2, mid + 1, end, left, right, value);
-        left_child || right_child
+    //Number of positions in the range whose value is exactly `value`.
+    pub fn count_exact(&self, range: impl RangeBounds<usize>, value: i64) -> usize {
+        self.try_count_exact(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
     }
+
+    pub fn try_count_exact(
+        &self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<usize, RangeError> {
+        self.tree
+            .try_count_matches(range, may_contain(value), is_exactly(value))
+    }
+
+    //Largest end such that pred holds for the max of start..end (an empty range always satisfies it),
+    //once false pred must stay false on larger maxima. O(log n), panics if start is out of bounds.
+    pub fn max_right(&self, start: usize, pred: impl Fn(i64) -> bool) -> usize {
+        self.tree
+            .max_right(start, |(_, info)| info.count == 0 || pred(info.max))
+    }
+
+    pub fn try_max_right(
+        &self,
+        start: usize,
+        pred: impl Fn(i64) -> bool,
+    ) -> Result<usize, RangeError> {
+        self.tree
+            .try_max_right(start, |(_, info)| info.count == 0 || pred(info.max))
+    }
+
+    //Smallest start such that pred holds for the max of start..end, same requirements of max_right.
+    pub fn min_left(&self, end: usize, pred: impl Fn(i64) -> bool) -> usize {
+        self.tree
+            .min_left(end, |(_, info)| info.count == 0 || pred(info.max))
+    }
+
+    pub fn try_min_left(
+        &self,
+        end: usize,
+        pred: impl Fn(i64) -> bool,
+    ) -> Result<usize, RangeError> {
+        self.tree
+            .try_min_left(end, |(_, info)| info.count == 0 || pred(info.max))
+    }
+
+    //First index in start..n whose value is at least x.
+    pub fn first_at_least(&self, start: usize, x: i64) -> Option<usize> {
+        let end = self.max_right(start, |max| max < x);
+        (end < self.tree.len()).then_some(end)
+    }
+}
+
+fn may_contain(value: i64) -> impl Fn(&Value) -> bool {
+    move |&((min, max), _)| min <= value && value <= max
+}
+
+fn is_exactly(value: i64) -> impl Fn(&Value) -> bool {
+    move |&((leaf, _), _)| leaf == value
 }
 
 //This function takes the list of segments and returns a vector that stores the number of segments at each point.
-pub fn sweep(array: &mut Vec<Vec<i64>>) -> Vec<i64> {
+pub fn sweep(array: &mut [Vec<i64>]) -> Vec<i64> {
     let mut max = -1;
     //Array that stores [start, +1] and [end, -1] for each vector, the size is of course 2n.
     let mut events = vec![[0, 0]; 2 * array.len()];
 
-    array.sort_by(|a, b| a.cmp(b));
+    array.sort();
 
     //Fill events array.
     for (index, el) in array.iter().enumerate() {
@@ -176,23 +333,338 @@ pub fn sweep(array: &mut Vec<Vec<i64>>) -> Vec<i64> {
     //The result array of the routine will be long as the rightmost point occopied by a segment. With this length we are able to store every point covered by segments.
     let mut result_array = vec![0; max as usize + 2];
 
-    events.sort_by(|a, b| a.cmp(b));
+    events.sort();
 
     let mut events_pointer = 0;
     let mut crt = 0;
 
     //By using the two pointers technique we are going trough both arrays so we are spending n+m time complexity. It's not n^2 since in the while loop we are iterating only trough the events len one time for the whole loop and not for each cycle.
-    for i in 0..result_array.len() {
+    for (i, point) in result_array.iter_mut().enumerate() {
         while events[events_pointer][0] == i as i64 {
             crt += events[events_pointer][1];
-            result_array[i] = crt;
             events_pointer += 1;
             if events_pointer == events.len() {
                 break;
             }
         }
-        result_array[i] = crt;
+        *point = crt;
     }
 
-    return result_array[0..result_array.len() - 1].to_vec();
+    result_array[0..result_array.len() - 1].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use segment_tree::rng::Rng;
+
+    fn tree_of(values: &[i64]) -> SegmentTree {
+        let mut tree = SegmentTree::new(values.len());
+        tree.build(values);
+        tree
+    }
+
+    #[test]
+    fn test_exact_queries_against_naive() {
+        let mut rng = Rng::new(38);
+        for _ in 0..40 {
+            let n = 1 + rng.below(50);
+            let mut naive: Vec<i64> = (0..n).map(|_| rng.below(8) as i64).collect();
+            let mut tree = tree_of(&naive);
+
+            for _ in 0..150 {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let range = a.min(b)..=a.max(b);
+                let value = rng.below(8) as i64;
+                if rng.below(3) == 0 {
+                    tree.update_range_with_value(range.clone(), value);
+                    naive[range].iter_mut().for_each(|x| *x = (*x).min(value));
+                    continue;
+                }
+
+                let positions: Vec<usize> = range.clone().filter(|&i| naive[i] == value).collect();
+                assert_eq!(
+                    tree.first_exact(range.clone(), value),
+                    positions.first().copied()
+                );
+                assert_eq!(
+                    tree.last_exact(range.clone(), value),
+                    positions.last().copied()
+                );
+                assert_eq!(tree.count_exact(range.clone(), value), positions.len());
+                assert_eq!(tree.is_there(range, value), !positions.is_empty() as i32);
+            }
+        }
+    }
+
+    //Worst case of the bounds above: every node has min < value < max, so the searches visit the whole tree.
+    //It must still answer correctly, and the positions of the values on both sides of the gap are still found.
+    #[test]
+    fn test_exact_queries_worst_case() {
+        let n = 1 << 16;
+        let alternating: Vec<i64> = (0..n as i64).map(|i| 2 * (i % 2)).collect();
+        let mut tree = tree_of(&alternating);
+
+        assert_eq!(tree.is_there(.., 1), 0);
+        assert_eq!(tree.first_exact(.., 1), None);
+        assert_eq!(tree.last_exact(.., 1), None);
+        assert_eq!(tree.count_exact(.., 1), 0);
+        assert_eq!(tree.count_exact(.., 0), n / 2);
+        assert_eq!(tree.first_exact(1.., 0), Some(2));
+        assert_eq!(tree.last_exact(..n - 1, 2), Some(n - 3));
+
+        //A chmin closes the gap: now every odd position holds exactly 1.
+        tree.update_range_with_value(.., 1);
+        assert_eq!(tree.first_exact(.., 1), Some(1));
+        assert_eq!(tree.last_exact(.., 1), Some(n - 1));
+        assert_eq!(tree.count_exact(.., 1), n / 2);
+        assert_eq!(tree.query_range(..), Some(1));
+    }
+
+    #[test]
+    fn test_binary_search_against_naive() {
+        let mut rng = Rng::new(39);
+        for _ in 0..30 {
+            let n = 1 + rng.below(40);
+            let mut naive: Vec<i64> = (0..n).map(|_| rng.below(20) as i64).collect();
+            let mut tree = tree_of(&naive);
+
+            for _ in 0..150 {
+                let x = rng.below(20) as i64;
+                if rng.below(3) == 0 {
+                    let a = rng.below(n);
+                    let b = rng.below(n);
+                    tree.update_range_with_value(a.min(b)..=a.max(b), x);
+                    naive[a.min(b)..=a.max(b)]
+                        .iter_mut()
+                        .for_each(|v| *v = (*v).min(x));
+                    continue;
+                }
+
+                let start = rng.below(n + 1);
+                let first = (start..n).find(|&i| naive[i] >= x);
+                assert_eq!(tree.first_at_least(start, x), first);
+                assert_eq!(tree.max_right(start, |max| max < x), first.unwrap_or(n));
+
+                let end = rng.below(n + 1);
+                let expected = (0..end).rev().find(|&i| naive[i] >= x).map_or(0, |i| i + 1);
+                assert_eq!(tree.min_left(end, |max| max < x), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_argmax_against_naive() {
+        let mut rng = Rng::new(40);
+        for _ in 0..30 {
+            let n = 1 + rng.below(50);
+            let mut naive: Vec<i64> = (0..n).map(|_| rng.below(20) as i64).collect();
+            let mut tree = tree_of(&naive);
+
+            for _ in 0..200 {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let range = a.min(b)..=a.max(b);
+                if rng.below(2) == 0 {
+                    let t = rng.below(20) as i64;
+                    tree.update_range_with_value(range.clone(), t);
+                    naive[range].iter_mut().for_each(|x| *x = (*x).min(t));
+                    continue;
+                }
+
+                let values = &naive[range.clone()];
+                let max = *values.iter().max().unwrap();
+                assert_eq!(
+                    tree.argmax(range.clone()),
+                    Some(range.start() + values.iter().position(|&x| x == max).unwrap())
+                );
+                assert_eq!(
+                    tree.max_count(range.clone()),
+                    values.iter().filter(|&&x| x == max).count()
+                );
+                assert_eq!(
+                    tree.second_max(range),
+                    values.iter().copied().filter(|&x| x < max).max()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let tree = tree_of(&[3, 1, 2]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert!(tree.try_first_exact(4.., 1).is_err());
+        assert!(tree.try_last_exact(reversed.clone(), 1).is_err());
+        assert!(tree.try_count_exact(0..=3, 1).is_err());
+        assert!(tree.try_is_there(reversed, 2).is_err());
+        assert_eq!(tree.count_exact(.., 1), 1);
+        assert!(tree.try_max_right(4, |_| true).is_err());
+        assert!(tree.try_min_left(4, |_| true).is_err());
+        assert!(tree.try_argmax(4..).is_err());
+        assert!(tree.try_max_count(..4).is_err());
+        assert!(tree.try_query_batch(&[(0, 3), (1, 4)]).is_err());
+    }
+
+    #[test]
+    fn test_to_vec_and_rebuild() {
+        let mut rng = Rng::new(46);
+        let mut naive: Vec<i64> = (0..100).map(|_| rng.below(50) as i64).collect();
+        let mut tree = tree_of(&naive);
+        for _ in 0..30 {
+            let a = rng.below(100);
+            let b = rng.below(100);
+            let value = rng.below(50) as i64;
+            tree.update_range_with_value(a.min(b)..=a.max(b), value);
+            naive[a.min(b)..=a.max(b)]
+                .iter_mut()
+                .for_each(|x| *x = (*x).min(value));
+        }
+        assert_eq!(tree.to_vec(), naive);
+        assert!(tree.leaves().eq(naive.iter().copied()));
+
+        tree.rebuild_from(&[4, 2, 4]);
+        assert_eq!(tree.to_vec(), [4, 2, 4]);
+        assert_eq!(tree.count_exact(.., 4), 2);
+    }
+
+    //Backtracking over random chmins: each level of the search takes a checkpoint, tries an update and undoes it.
+    #[test]
+    fn test_rollback_against_naive() {
+        let mut rng = Rng::new(47);
+        for _ in 0..30 {
+            let n = 1 + rng.below(50);
+            let mut naive: Vec<i64> = (0..n).map(|_| rng.below(20) as i64).collect();
+            let mut tree = tree_of(&naive);
+            let mut saved = Vec::new();
+
+            for _ in 0..300 {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let range = a.min(b)..=a.max(b);
+                match rng.below(5) {
+                    0 => saved.push((tree.checkpoint(), naive.clone())),
+                    1 => {
+                        if let Some((checkpoint, before)) = saved.pop() {
+                            tree.rollback(checkpoint);
+                            naive = before;
+                        }
+                    }
+                    2 => {
+                        let value = rng.below(20) as i64;
+                        tree.update_range_with_value(range.clone(), value);
+                        naive[range].iter_mut().for_each(|x| *x = (*x).min(value));
+                    }
+                    _ => {
+                        let values = &naive[range.clone()];
+                        let max = values.iter().max().copied();
+                        assert_eq!(tree.query_range(range.clone()), max);
+                        assert_eq!(
+                            tree.max_count(range.clone()),
+                            values.iter().filter(|&&x| Some(x) == max).count()
+                        );
+                        assert_eq!(
+                            tree.first_exact(range.clone(), 5),
+                            range.clone().find(|&i| naive[i] == 5)
+                        );
+                    }
+                }
+            }
+            while let Some((checkpoint, before)) = saved.pop() {
+                tree.rollback(checkpoint);
+                assert_eq!(tree.to_vec(), before);
+            }
+        }
+    }
+
+    //The exact searches and argmax must not see the leaves past the end of a grown tree.
+    #[test]
+    fn test_push_pop_against_naive() {
+        let mut rng = Rng::new(48);
+        let mut tree = SegmentTree::new(0);
+        let mut naive = Vec::new();
+        for _ in 0..1500 {
+            let n = naive.len();
+            match rng.below(5) {
+                0 => assert_eq!(tree.pop(), naive.pop()),
+                1 => {
+                    let a = rng.below(n + 1);
+                    let b = rng.below(n + 1);
+                    let value = rng.below(10) as i64;
+                    tree.update_range_with_value(a.min(b)..a.max(b), value);
+                    naive[a.min(b)..a.max(b)]
+                        .iter_mut()
+                        .for_each(|x| *x = (*x).min(value));
+                }
+                2 => {
+                    let value = rng.below(10) as i64;
+                    assert_eq!(
+                        tree.last_exact(.., value),
+                        naive.iter().rposition(|&x| x == value)
+                    );
+                    assert_eq!(
+                        tree.count_exact(.., value),
+                        naive.iter().filter(|&&x| x == value).count()
+                    );
+                }
+                _ => {
+                    let value = rng.below(10) as i64;
+                    tree.push(value);
+                    naive.push(value);
+                }
+            }
+            assert_eq!(tree.len(), naive.len());
+            assert_eq!(tree.query_range(..), naive.iter().max().copied());
+            let max = naive.iter().max();
+            assert_eq!(
+                tree.argmax(..),
+                max.and_then(|max| naive.iter().position(|x| x == max))
+            );
+        }
+
+        tree.resize(0, 0);
+        assert_eq!(tree.query_range(..), None);
+        tree.resize(4, 3);
+        assert_eq!(tree.to_vec(), [3; 4]);
+        assert_eq!(tree.first_at_least(0, 4), None);
+    }
+
+    #[test]
+    fn test_query_batch() {
+        fn is_sync<T: Sync>(_: &T) -> bool {
+            true
+        }
+        let mut rng = Rng::new(45);
+        let mut naive: Vec<i64> = (0..300).map(|_| rng.below(100) as i64).collect();
+        let mut tree = tree_of(&naive);
+        //Chmins on prefixes leave pending tags all over the tree, the batch must see them without pushing.
+        for end in (1..=300).rev().step_by(7) {
+            let value = rng.below(100) as i64;
+            tree.update_range_with_value(..end, value);
+            naive[..end].iter_mut().for_each(|x| *x = (*x).min(value));
+        }
+        let ranges: Vec<(usize, usize)> = (0..=300).map(|start| (start, 300)).collect();
+        let expected: Vec<Option<i64>> = ranges
+            .iter()
+            .map(|&(start, end)| naive[start..end].iter().max().copied())
+            .collect();
+        assert!(is_sync(&tree));
+        assert_eq!(tree.query_batch(&ranges), expected);
+        assert_eq!(tree.query_batch(&ranges), expected);
+    }
+
+    #[test]
+    fn test_empty_ranges() {
+        let mut tree = tree_of(&[3, 1, 2]);
+        tree.update_range_with_value(1..1, 0);
+        assert_eq!(tree.query_range(1..1), None);
+        assert_eq!(tree.query_range(..), Some(3));
+        assert_eq!(tree.first_exact(3.., 1), None);
+        assert_eq!(tree.count_exact(2..2, 2), 0);
+        assert_eq!(tree.is_there(0..0, 3), 0);
+        assert_eq!(tree.argmax(3..), None);
+        assert_eq!(tree.max_count(..0), 0);
+    }
 }
@@ -1,9 +1,11 @@
 use segment_2::sweep;
 use segment_2::SegmentTree;
 
+#[allow(clippy::items_after_test_module)]
 #[cfg(test)]
 mod tests {
     use super::*;
+    use segment_tree::range::one_based;
     use std::fs;
     use std::path::Path;
     use std::path::PathBuf;
@@ -12,7 +14,8 @@ mod tests {
     #[test]
     fn run_tests() {
         let directory_path = "src/Testset_handson2_2324_p1/";
-        let mut tree: SegmentTree;
+        //One tree for all the files, rebuilt on each input.
+        let mut tree = SegmentTree::new(0);
 
         //Hardcoded, should read the number of txt files in the folder instead.
         for i in 0..=10 {
@@ -48,25 +51,27 @@ mod tests {
 
             let input_values = &all_input_values[1];
             let mut output_index = 0;
-            tree = SegmentTree::new(input_values.len());
-            tree.build(input_values);
+            tree.rebuild_from(input_values);
             println!("working on input: {}", input_filename);
 
             for line in all_input_values.iter().skip(2) {
                 if line[0] == 0 {
                     println!("update {:?}", line);
-                    tree.update_range_with_value(line[1] as usize, line[2] as usize, line[3]);
+                    tree.update_range_with_value(
+                        one_based(line[1] as usize, line[2] as usize),
+                        line[3],
+                    );
                 } else if line[0] == 1 {
                     println!(
-                        "{:?} my:{}, first value {}, second value {}",
+                        "{:?} my:{:?}, first value {}, second value {}",
                         output_array[output_index],
-                        tree.query_range(line[1] as usize, line[2] as usize),
+                        tree.query_range(one_based(line[1] as usize, line[2] as usize)),
                         line[1],
                         line[2],
                     );
                     assert!(
-                        tree.query_range(line[1] as usize, line[2] as usize)
-                            == output_array[output_index]
+                        tree.query_range(one_based(line[1] as usize, line[2] as usize))
+                            == Some(output_array[output_index])
                     );
                     output_index += 1;
                 }
@@ -78,6 +83,7 @@ mod tests {
     #[test]
     fn test2() {
         let directory_path = "src/Testset_handson2_2324_p2/";
+        let mut tree = SegmentTree::new(0);
 
         //Hardcoded, should read the number of txt files in the folder instead.
         for i in 0..=7 {
@@ -115,8 +121,7 @@ mod tests {
             }
 
             let leaves_array = sweep(&mut input_vectors);
-            let mut tree = SegmentTree::new(leaves_array.len());
-            tree.build(&leaves_array);
+            tree.rebuild_from(&leaves_array);
 
             for (index, query) in (all_input_values[0][0] + 1
                 ..=all_input_values[0][0] + all_input_values[0][1])
@@ -125,19 +130,19 @@ mod tests {
                 println!(
                     "returned: {} expected: {}",
                     tree.is_there(
-                        all_input_values[query as usize][0] as usize,
-                        all_input_values[query as usize][1] as usize,
+                        all_input_values[query as usize][0] as usize
+                            ..=all_input_values[query as usize][1] as usize,
                         all_input_values[query as usize][2]
                     ),
-                    output_array[index as usize] as i32
+                    output_array[index] as i32
                 );
 
                 assert!(
                     tree.is_there(
-                        all_input_values[query as usize][0] as usize,
-                        all_input_values[query as usize][1] as usize,
+                        all_input_values[query as usize][0] as usize
+                            ..=all_input_values[query as usize][1] as usize,
                         all_input_values[query as usize][2]
-                    ) == output_array[index as usize] as i32
+                    ) == output_array[index] as i32
                 );
             }
         }
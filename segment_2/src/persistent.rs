@@ -0,0 +1,329 @@
+use segment_tree::range::{to_half_open, RangeError};
+use std::ops::{Range, RangeBounds};
+
+//Handle to one version of a PersistentSegmentTree, version 0 is the array passed to new
+//and the k-th update creates version k. A Version is only valid on the tree that issued it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(usize);
+
+impl Version {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    //Max of the subtree, with the tag of this node and of the nodes below already applied.
+    max: i64,
+    //Permanent chmin tag: it is never pushed, it holds for the whole subtree.
+    tag: Option<i64>,
+    left: usize,
+    right: usize,
+}
+
+//Persistent variant of SegmentTree (range max, range chmin) with path copying.
+//An update copies only the nodes it visits, so every old version stays valid and shares the untouched subtrees with the new one.
+//The tags are permanent instead of lazy: pushing them would need to copy the children too,
+//so a query takes the min of the tags met on the way down and applies it to the nodes it takes.
+#[derive(Debug)]
+pub struct PersistentSegmentTree {
+    size: usize,
+    nodes: Vec<Node>,
+    roots: Vec<usize>,
+}
+
+impl PersistentSegmentTree {
+    pub fn new(arr: &[i64]) -> Self {
+        let mut tree = Self {
+            size: arr.len(),
+            nodes: Vec::with_capacity(2 * arr.len()),
+            roots: Vec::new(),
+        };
+        //The root of an empty tree is never read, every non empty range is out of bounds.
+        let root = if arr.is_empty() {
+            tree.push_node(0, 0, 0)
+        } else {
+            tree.build(arr, 0, arr.len() - 1)
+        };
+        tree.roots.push(root);
+        tree
+    }
+
+    fn push_node(&mut self, max: i64, left: usize, right: usize) -> usize {
+        self.nodes.push(Node {
+            max,
+            tag: None,
+            left,
+            right,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn build(&mut self, arr: &[i64], low: usize, high: usize) -> usize {
+        if low == high {
+            return self.push_node(arr[low], 0, 0);
+        }
+        let mid = (low + high) / 2;
+        let left = self.build(arr, low, mid);
+        let right = self.build(arr, mid + 1, high);
+        let max = self.nodes[left].max.max(self.nodes[right].max);
+        self.push_node(max, left, right)
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    //Number of versions, the initial one included.
+    pub fn versions(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn latest(&self) -> Version {
+        Version(self.roots.len() - 1)
+    }
+
+    //Returns the version with the given index, if it exists.
+    pub fn version(&self, index: usize) -> Option<Version> {
+        (index < self.roots.len()).then_some(Version(index))
+    }
+
+    //Number of nodes allocated by all the versions together.
+    pub fn arena_len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    //Max in the range on the latest version, positions are 0 indexed, None if the range is empty.
+    //Panics if the range is reversed or out of bounds.
+    pub fn query_range(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.query_range_at(self.latest(), range)
+    }
+
+    pub fn try_query_range(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i64>, RangeError> {
+        self.try_query_range_at(self.latest(), range)
+    }
+
+    //Max in the range as it was in `version`, None if the range is empty. Panics if the range is reversed or out of bounds.
+    //version must come from this tree: the try_ variant checks only the range, so a Version of another tree
+    //panics if that tree has more versions and otherwise answers on the version with the same index.
+    pub fn query_range_at(&self, version: Version, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.try_query_range_at(version, range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_query_range_at(
+        &self,
+        version: Version,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i64>, RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start == end {
+            return Ok(None);
+        }
+        Ok(self.query_rec(
+            self.roots[version.0],
+            0,
+            self.size - 1,
+            start,
+            end - 1,
+            None,
+        ))
+    }
+
+    //`above` is the min of the tags of the ancestors of v, None if none of them has a tag.
+    //Returns None if [low, high] and [left, right] are disjoint.
+    fn query_rec(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        above: Option<i64>,
+    ) -> Option<i64> {
+        if right < low || high < left {
+            return None;
+        }
+        let node = &self.nodes[v];
+        if left <= low && high <= right {
+            return Some(chmin(node.max, above));
+        }
+        let above = min_tag(above, node.tag);
+        let mid = (low + high) / 2;
+        let left_max = self.query_rec(node.left, low, mid, left, right, above);
+        let right_max = self.query_rec(node.right, mid + 1, high, left, right, above);
+        left_max.max(right_max)
+    }
+
+    //Sets a[i] = min(a[i], value) for every i in the range on top of the latest version and returns the new version.
+    //An empty range still creates a version, equal to the previous one. Panics if the range is reversed or out of bounds.
+    pub fn update_range_with_value(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Version {
+        self.try_update_range_with_value(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_update_range_with_value(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<Version, RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start == end {
+            self.roots.push(self.roots[self.latest().0]);
+            return Ok(self.latest());
+        }
+        let root = self.update_rec(
+            self.roots[self.latest().0],
+            0,
+            self.size - 1,
+            start,
+            end - 1,
+            value,
+        );
+        self.roots.push(root);
+        Ok(self.latest())
+    }
+
+    //Returns the copy of v with the update applied, v itself is never modified.
+    fn update_rec(
+        &mut self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        value: i64,
+    ) -> usize {
+        if right < low || high < left {
+            return v;
+        }
+        let mut node = self.nodes[v].clone();
+        if left <= low && high <= right {
+            node.max = node.max.min(value);
+            node.tag = min_tag(node.tag, Some(value));
+        } else {
+            let mid = (low + high) / 2;
+            node.left = self.update_rec(node.left, low, mid, left, right, value);
+            node.right = self.update_rec(node.right, mid + 1, high, left, right, value);
+            node.max = self.nodes[node.left].max.max(self.nodes[node.right].max);
+            node.max = chmin(node.max, node.tag);
+        }
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+}
+
+//The tighter of two chmin tags, None means no tag.
+fn min_tag(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        _ => a.or(b),
+    }
+}
+
+fn chmin(value: i64, tag: Option<i64>) -> i64 {
+    tag.map_or(value, |tag| value.min(tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use segment_tree::rng::Rng;
+
+    #[test]
+    fn test_history_against_naive() {
+        let mut rng = Rng::new(35);
+        for size in 1..30 {
+            let mut naive: Vec<i64> = (0..size).map(|_| rng.below(100) as i64).collect();
+            let mut history = vec![naive.clone()];
+            let mut tree = PersistentSegmentTree::new(&naive);
+
+            for _ in 0..60 {
+                let a = rng.below(size);
+                let b = rng.below(size);
+                let value = rng.below(100) as i64;
+                let version = tree.update_range_with_value(a.min(b)..=a.max(b), value);
+                for x in &mut naive[a.min(b)..=a.max(b)] {
+                    *x = (*x).min(value);
+                }
+                history.push(naive.clone());
+                assert_eq!(version.index(), history.len() - 1);
+            }
+
+            for _ in 0..200 {
+                let version = tree.version(rng.below(history.len())).unwrap();
+                let a = rng.below(size);
+                let b = rng.below(size);
+                let expected = history[version.index()][a.min(b)..=a.max(b)]
+                    .iter()
+                    .max()
+                    .copied();
+                assert_eq!(tree.query_range_at(version, a.min(b)..=a.max(b)), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_old_versions_are_untouched() {
+        let mut tree = PersistentSegmentTree::new(&[5, 1, 4, 3, 2]);
+        let first = tree.latest();
+        let second = tree.update_range_with_value(0..3, 2);
+        let third = tree.update_range_with_value(.., 1);
+
+        assert_eq!(tree.query_range_at(first, ..), Some(5));
+        assert_eq!(tree.query_range_at(second, ..), Some(3));
+        assert_eq!(tree.query_range_at(second, ..3), Some(2));
+        assert_eq!(tree.query_range_at(third, ..), Some(1));
+        assert_eq!(tree.query_range(..), Some(1));
+        assert_eq!(tree.versions(), 3);
+        assert!(tree.version(3).is_none());
+    }
+
+    #[test]
+    fn test_path_copying_is_logarithmic() {
+        let size = 1 << 12;
+        let mut tree = PersistentSegmentTree::new(&vec![0; size]);
+        let initial = tree.arena_len();
+        tree.update_range_with_value(17..=17, -1);
+        //A point update copies one node per level.
+        assert_eq!(tree.arena_len() - initial, 13);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut tree = PersistentSegmentTree::new(&[5, 1, 4]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert!(tree.try_update_range_with_value(reversed, 0).is_err());
+        assert!(tree.try_query_range(0..4).is_err());
+        assert_eq!(tree.versions(), 1);
+    }
+
+    #[test]
+    fn test_full_domain() {
+        let mut tree = PersistentSegmentTree::new(&[i64::MIN, i64::MAX, 0]);
+        let empty = tree.update_range_with_value(1..1, 0);
+        assert_eq!(tree.query_range_at(empty, ..), Some(i64::MAX));
+        assert_eq!(tree.query_range(2..2), None);
+        assert_eq!(tree.query_range(..1), Some(i64::MIN));
+        //A chmin with i64::MAX is a real tag that changes nothing.
+        tree.update_range_with_value(.., i64::MAX);
+        assert_eq!(tree.query_range(..), Some(i64::MAX));
+        tree.update_range_with_value(1..=1, i64::MIN);
+        assert_eq!(tree.query_range(..2), Some(i64::MIN));
+        assert_eq!(tree.query_range(..), Some(0));
+        assert_eq!(tree.versions(), 4);
+    }
+}
@@ -0,0 +1,308 @@
+use segment_tree::range::{to_inclusive, RangeError};
+use std::ops::{RangeBounds, RangeInclusive};
+
+#[derive(Debug, Clone)]
+struct Node {
+    //Added to every point of the subtree. It is never pushed, a missing child has all its points at 0.
+    add: i64,
+    //Min and max of the subtree, add of this node included.
+    min: i64,
+    max: i64,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Node {
+            add: 0,
+            min: 0,
+            max: 0,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+//Segment tree over every i64 coordinate, starting with all the points at 0, with range add and range min/max.
+//The nodes are created only when an update goes through them, so k updates use O(k * 64) nodes
+//no matter how far the coordinates are. Used for the coverage of the segments: each segment adds 1 to its points.
+#[derive(Debug)]
+pub struct SparseSegmentTree {
+    nodes: Vec<Node>,
+}
+
+impl Default for SparseSegmentTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//Midpoint of [low, high] without overflowing, the children are [low, mid] and [mid + 1, high].
+fn mid(low: i64, high: i64) -> i64 {
+    ((low as i128 + high as i128).div_euclid(2)) as i64
+}
+
+impl SparseSegmentTree {
+    pub fn new() -> Self {
+        SparseSegmentTree {
+            nodes: vec![Node::new()],
+        }
+    }
+
+    //Builds the coverage of the segments: every point holds the number of segments [start, end] that contain it.
+    pub fn from_segments(segments: &[(i64, i64)]) -> Self {
+        let mut tree = Self::new();
+        for &(start, end) in segments {
+            tree.add(start..=end, 1);
+        }
+        tree
+    }
+
+    //Number of allocated nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    //Adds delta to every point in the range. Panics if the range is reversed.
+    pub fn add(&mut self, range: impl RangeBounds<i64>, delta: i64) {
+        self.try_add(range, delta)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_add(&mut self, range: impl RangeBounds<i64>, delta: i64) -> Result<(), RangeError> {
+        if let Some(range) = to_inclusive(range)? {
+            self.add_rec(0, i64::MIN, i64::MAX, &range, delta);
+        }
+        Ok(())
+    }
+
+    fn add_rec(&mut self, v: usize, low: i64, high: i64, range: &RangeInclusive<i64>, delta: i64) {
+        if *range.start() <= low && high <= *range.end() {
+            let node = &mut self.nodes[v];
+            node.add += delta;
+            node.min += delta;
+            node.max += delta;
+            return;
+        }
+        let mid = mid(low, high);
+        if *range.start() <= mid {
+            let left = self.child(v, true);
+            self.add_rec(left, low, mid, range, delta);
+        }
+        if mid < *range.end() {
+            let right = self.child(v, false);
+            self.add_rec(right, mid + 1, high, range, delta);
+        }
+
+        let (left_min, left_max) = self.bounds(self.nodes[v].left);
+        let (right_min, right_max) = self.bounds(self.nodes[v].right);
+        let node = &mut self.nodes[v];
+        node.min = left_min.min(right_min) + node.add;
+        node.max = left_max.max(right_max) + node.add;
+    }
+
+    //Returns the left or right child of v, creating it if it is missing.
+    fn child(&mut self, v: usize, is_left: bool) -> usize {
+        let existing = if is_left {
+            self.nodes[v].left
+        } else {
+            self.nodes[v].right
+        };
+        if let Some(child) = existing {
+            return child;
+        }
+        self.nodes.push(Node::new());
+        let child = self.nodes.len() - 1;
+        if is_left {
+            self.nodes[v].left = Some(child);
+        } else {
+            self.nodes[v].right = Some(child);
+        }
+        child
+    }
+
+    //Min and max of a child, a missing child has all its points at 0.
+    fn bounds(&self, child: Option<usize>) -> (i64, i64) {
+        child.map_or((0, 0), |c| (self.nodes[c].min, self.nodes[c].max))
+    }
+
+    //Max in the range, None if it is empty. Panics if the range is reversed.
+    pub fn max(&self, range: impl RangeBounds<i64>) -> Option<i64> {
+        self.try_max(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_max(&self, range: impl RangeBounds<i64>) -> Result<Option<i64>, RangeError> {
+        Ok(self.min_max(range)?.map(|(_, max)| max))
+    }
+
+    //Min in the range, None if it is empty. Panics if the range is reversed.
+    pub fn min(&self, range: impl RangeBounds<i64>) -> Option<i64> {
+        self.try_min(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_min(&self, range: impl RangeBounds<i64>) -> Result<Option<i64>, RangeError> {
+        Ok(self.min_max(range)?.map(|(min, _)| min))
+    }
+
+    fn min_max(&self, range: impl RangeBounds<i64>) -> Result<Option<(i64, i64)>, RangeError> {
+        Ok(to_inclusive(range)?
+            .map(|range| self.min_max_rec(Some(0), i64::MIN, i64::MAX, &range, 0)))
+    }
+
+    //Returns (min, max) of the intersection of [low, high] with the range, `above` is the sum of the adds of the ancestors.
+    //The caller only visits nodes that intersect the range.
+    fn min_max_rec(
+        &self,
+        v: Option<usize>,
+        low: i64,
+        high: i64,
+        range: &RangeInclusive<i64>,
+        above: i64,
+    ) -> (i64, i64) {
+        let Some(v) = v else {
+            return (above, above);
+        };
+        let node = &self.nodes[v];
+        if *range.start() <= low && high <= *range.end() {
+            return (node.min + above, node.max + above);
+        }
+        let above = above + node.add;
+        let mid = mid(low, high);
+        let mut result = (i64::MAX, i64::MIN);
+        if *range.start() <= mid {
+            let (min, max) = self.min_max_rec(node.left, low, mid, range, above);
+            result = (result.0.min(min), result.1.max(max));
+        }
+        if mid < *range.end() {
+            let (min, max) = self.min_max_rec(node.right, mid + 1, high, range, above);
+            result = (result.0.min(min), result.1.max(max));
+        }
+        result
+    }
+
+    //Returns 1 if there is a point in the range whose value is exactly `value`, 0 otherwise. Panics if the range is reversed.
+    //The search does not enter the subtrees whose min and max do not contain the value.
+    pub fn is_there(&self, range: impl RangeBounds<i64>, value: i64) -> i32 {
+        self.try_is_there(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_is_there(
+        &self,
+        range: impl RangeBounds<i64>,
+        value: i64,
+    ) -> Result<i32, RangeError> {
+        let found = to_inclusive(range)?
+            .is_some_and(|range| self.is_there_rec(Some(0), i64::MIN, i64::MAX, &range, value, 0));
+        Ok(if found { 1 } else { 0 })
+    }
+
+    fn is_there_rec(
+        &self,
+        v: Option<usize>,
+        low: i64,
+        high: i64,
+        range: &RangeInclusive<i64>,
+        value: i64,
+        above: i64,
+    ) -> bool {
+        let Some(v) = v else {
+            return above == value;
+        };
+        let node = &self.nodes[v];
+        if value < node.min + above || node.max + above < value {
+            return false;
+        }
+        if low == high {
+            return true;
+        }
+        let above = above + node.add;
+        let mid = mid(low, high);
+        (*range.start() <= mid && self.is_there_rec(node.left, low, mid, range, value, above))
+            || (mid < *range.end()
+                && self.is_there_rec(node.right, mid + 1, high, range, value, above))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sweep;
+    use segment_tree::rng::Rng;
+
+    #[test]
+    fn test_against_sweep() {
+        let mut rng = Rng::new(36);
+        for _ in 0..50 {
+            let mut segments: Vec<Vec<i64>> = (0..rng.below(20) + 1)
+                .map(|_| {
+                    let a = rng.below(40) as i64;
+                    let b = rng.below(40) as i64;
+                    vec![a.min(b), a.max(b)]
+                })
+                .collect();
+            let pairs: Vec<(i64, i64)> = segments.iter().map(|s| (s[0], s[1])).collect();
+            let tree = SparseSegmentTree::from_segments(&pairs);
+            let counts = sweep(&mut segments);
+
+            for _ in 0..100 {
+                let a = rng.below(counts.len());
+                let b = rng.below(counts.len());
+                let range = a.min(b)..=a.max(b);
+                let coordinates = a.min(b) as i64..=a.max(b) as i64;
+                let value = rng.below(5) as i64;
+
+                let expected = counts[range.clone()].contains(&value) as i32;
+                assert_eq!(tree.is_there(coordinates.clone(), value), expected);
+                assert_eq!(
+                    tree.max(coordinates.clone()),
+                    counts[range.clone()].iter().max().copied()
+                );
+                assert_eq!(tree.min(coordinates), counts[range].iter().min().copied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_coordinates() {
+        let big = 1_000_000_000_000_000_000;
+        let tree = SparseSegmentTree::from_segments(&[
+            (0, big),
+            (big - 5, big + 5),
+            (i64::MIN, i64::MIN + 1),
+            (i64::MAX, i64::MAX),
+        ]);
+
+        assert_eq!(tree.max(..), Some(2));
+        assert_eq!(tree.min(..), Some(0));
+        assert_eq!(tree.max(big - 5..=big), Some(2));
+        assert_eq!(tree.max(big + 1..), Some(1));
+        assert_eq!(tree.is_there(big + 6..i64::MAX, 0), 1);
+        assert_eq!(tree.is_there(big + 6..i64::MAX, 1), 0);
+        assert_eq!(tree.is_there(i64::MAX.., 1), 1);
+        assert_eq!(tree.is_there(..=i64::MIN, 1), 1);
+        assert_eq!(tree.is_there(-10..0, 1), 0);
+        //Every segment creates at most two nodes per level.
+        assert!(tree.node_count() <= 4 * 2 * 64);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut tree = SparseSegmentTree::new();
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..=1;
+        assert!(tree.try_add(reversed.clone(), 1).is_err());
+        assert!(tree.try_is_there(reversed, 0).is_err());
+        assert_eq!(tree.node_count(), 1);
+        //Empty ranges are fine: the update does nothing and the queries have no value.
+        assert_eq!(tree.try_add(5..5, 1), Ok(()));
+        assert_eq!(tree.try_max(..i64::MIN), Ok(None));
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 3..=2;
+        assert_eq!(tree.try_is_there(empty, 0), Ok(0));
+        assert_eq!(tree.node_count(), 1);
+    }
+}
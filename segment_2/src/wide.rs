@@ -0,0 +1,208 @@
+use segment_tree::range::{to_half_open, RangeError};
+use std::ops::{Range, RangeBounds};
+
+//Number of children of a node. 16 i64 are two cache lines, scanned in one go.
+const B: usize = 16;
+
+//Static B-ary segment tree for range max, for read-heavy workloads with rare updates.
+//The levels are stored one after the other in a single Vec, from the leaves up: the node i of a level is the max of the
+//nodes B * i..B * i + B of the level below, so the children of a node are contiguous and a query scans at most
+//2 (B - 1) values per level on log_B n levels instead of following pointers down log_2 n levels.
+//There are no lazy tags: a range chmin writes the leaves and recomputes the blocks above them.
+#[derive(Debug)]
+pub struct WideSegmentTree {
+    size: usize,
+    nodes: Vec<i64>,
+    //levels[k] is the range of nodes of the level k, levels[0] are the leaves and the last level is the root.
+    levels: Vec<Range<usize>>,
+}
+
+impl WideSegmentTree {
+    pub fn new(arr: &[i64]) -> Self {
+        let mut nodes = arr.to_vec();
+        let mut levels = Vec::new();
+        levels.push(0..arr.len());
+        while levels.last().unwrap().len() > 1 {
+            let below = levels.last().unwrap().clone();
+            let start = nodes.len();
+            for block in below.clone().step_by(B) {
+                let max = *nodes[block..(block + B).min(below.end)]
+                    .iter()
+                    .max()
+                    .unwrap();
+                nodes.push(max);
+            }
+            levels.push(start..nodes.len());
+        }
+        WideSegmentTree {
+            size: arr.len(),
+            nodes,
+            levels,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    //Max in the range, positions are 0 indexed, None if the range is empty. O(B log_B n).
+    //Panics if the range is reversed or out of bounds.
+    pub fn query_range(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.try_query_range(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    //At every level the two partial blocks at the borders are scanned and the blocks in between are left to the level
+    //above, until the range fits in one block.
+    pub fn try_query_range(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i64>, RangeError> {
+        let Range { mut start, mut end } = to_half_open(range, self.size)?;
+        let mut max = None;
+        for level in &self.levels {
+            let nodes = &self.nodes[level.clone()];
+            let (inner_start, inner_end) = (start.div_ceil(B), end / B);
+            if inner_start >= inner_end {
+                return Ok(max.max(nodes[start..end].iter().max().copied()));
+            }
+            max = max
+                .max(nodes[start..inner_start * B].iter().max().copied())
+                .max(nodes[inner_end * B..end].iter().max().copied());
+            (start, end) = (inner_start, inner_end);
+        }
+        Ok(max)
+    }
+
+    //Sets a[index] = value, O(B log_B n). Panics if the index is out of bounds.
+    pub fn set(&mut self, index: usize, value: i64) {
+        self.try_set(index, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_set(&mut self, index: usize, value: i64) -> Result<(), RangeError> {
+        let range = to_half_open(index..=index, self.size)?;
+        self.nodes[index] = value;
+        self.rebuild(range);
+        Ok(())
+    }
+
+    //Sets a[i] = min(a[i], value) for every i in the range. The leaves are written one by one and the blocks above
+    //them are recomputed, O(len + B log_B n): cheap for short ranges, a full rebuild for the whole array.
+    //Panics if the range is reversed or out of bounds.
+    pub fn update_range_with_value(&mut self, range: impl RangeBounds<usize>, value: i64) {
+        self.try_update_range_with_value(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_update_range_with_value(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<(), RangeError> {
+        let range = to_half_open(range, self.size)?;
+        self.nodes[range.clone()]
+            .iter_mut()
+            .for_each(|x| *x = (*x).min(value));
+        self.rebuild(range);
+        Ok(())
+    }
+
+    //Recomputes the ancestors of the leaves in the range, level by level.
+    fn rebuild(&mut self, leaves: Range<usize>) {
+        if leaves.is_empty() {
+            return;
+        }
+        let (mut first, mut last) = (leaves.start, leaves.end - 1);
+        for pair in self.levels.windows(2) {
+            let (below, above) = (&pair[0], &pair[1]);
+            (first, last) = (first / B, last / B);
+            for parent in first..=last {
+                let children =
+                    below.start + parent * B..(below.start + parent * B + B).min(below.end);
+                self.nodes[above.start + parent] = *self.nodes[children].iter().max().unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SegmentTree;
+    use segment_tree::rng::Rng;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = Rng::new(44);
+        //Sizes around the powers of B, where the number of levels changes.
+        for n in [1, 2, 15, 16, 17, 255, 256, 257, 1000, 4097] {
+            let mut naive: Vec<i64> = (0..n).map(|_| rng.below(1000) as i64 - 500).collect();
+            let mut wide = WideSegmentTree::new(&naive);
+
+            for _ in 0..300 {
+                let a = rng.below(n);
+                let b = rng.below(n + 1);
+                let range = a.min(b)..a.max(b);
+                let value = rng.below(1000) as i64 - 500;
+                match rng.below(4) {
+                    0 => {
+                        wide.update_range_with_value(range.clone(), value);
+                        naive[range].iter_mut().for_each(|x| *x = (*x).min(value));
+                    }
+                    1 => {
+                        wide.set(a, value);
+                        naive[a] = value;
+                    }
+                    _ => assert_eq!(
+                        wide.query_range(range.clone()),
+                        naive[range].iter().max().copied()
+                    ),
+                }
+            }
+            assert_eq!(wide.query_range(..), naive.iter().max().copied());
+        }
+    }
+
+    //Same range argument and same answers of SegmentTree::query_range, so one can replace the other.
+    #[test]
+    fn test_same_api_as_segment_tree() {
+        let values = [5, 1, 4, 3, 2, 7, 4];
+        let wide = WideSegmentTree::new(&values);
+        let mut tree = SegmentTree::new(values.len());
+        tree.build(&values);
+        for start in 0..=values.len() {
+            for end in start..=values.len() {
+                assert_eq!(wide.query_range(start..end), tree.query_range(start..end));
+            }
+        }
+    }
+
+    #[test]
+    fn test_levels() {
+        let wide = WideSegmentTree::new(&vec![0; 257]);
+        //257 leaves, 17 blocks of 16, 2 blocks and the root.
+        let lengths: Vec<usize> = wide.levels.iter().map(|level| level.len()).collect();
+        assert_eq!(lengths, [257, 17, 2, 1]);
+        assert_eq!(wide.nodes.len(), 257 + 17 + 2 + 1);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut wide = WideSegmentTree::new(&[3, i64::MIN, 2]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert!(wide.try_query_range(reversed).is_err());
+        assert!(wide.try_update_range_with_value(0..4, 0).is_err());
+        assert!(wide.try_set(3, 0).is_err());
+        assert_eq!(wide.query_range(1..1), None);
+        assert_eq!(wide.query_range(1..2), Some(i64::MIN));
+
+        let empty = WideSegmentTree::new(&[]);
+        assert_eq!(empty.query_range(..), None);
+    }
+}
@@ -0,0 +1,173 @@
+use segment_tree::lazy::LazySegmentTree;
+use segment_tree::ops::{Assign, MinMax, Sum};
+use segment_tree::range::{to_half_open, RangeError};
+use std::ops::RangeBounds;
+
+//((min, max), sum) of a range.
+type Node = ((i64, i64), i64);
+
+//Range assignment (a[i] = t) with range max, min and sum queries.
+//Every node keeps (min, max) and the sum of its range, in i64 so that the sum of many i32 does not overflow.
+//An assignment sets the whole node, and a newer one replaces the pending one (Assign).
+#[derive(Debug)]
+pub struct SegmentTree {
+    tree: LazySegmentTree<(MinMax<i64>, Sum<i64>), Assign<i64>>,
+}
+
+impl SegmentTree {
+    pub fn new(array: &[i32]) -> Self {
+        SegmentTree {
+            tree: LazySegmentTree::new(
+                &array
+                    .iter()
+                    .map(|&x| ((x as i64, x as i64), x as i64))
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    //Max in the range, positions are 0 indexed, None if the range is empty.
+    //Panics if the range is reversed or out of bounds.
+    pub fn max_query(&self, range: impl RangeBounds<usize>) -> Option<i32> {
+        self.try_max_query(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_max_query(&self, range: impl RangeBounds<usize>) -> Result<Option<i32>, RangeError> {
+        Ok(self.try_query(range)?.map(|((_, max), _)| max as i32))
+    }
+
+    //Min in the range, None if the range is empty. Panics if the range is reversed or out of bounds.
+    pub fn min_query(&self, range: impl RangeBounds<usize>) -> Option<i32> {
+        self.try_min_query(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_min_query(&self, range: impl RangeBounds<usize>) -> Result<Option<i32>, RangeError> {
+        Ok(self.try_query(range)?.map(|((min, _), _)| min as i32))
+    }
+
+    //Sum of the range, 0 if the range is empty. Panics if the range is reversed or out of bounds.
+    pub fn sum_query(&self, range: impl RangeBounds<usize>) -> i64 {
+        self.try_sum_query(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_sum_query(&self, range: impl RangeBounds<usize>) -> Result<i64, RangeError> {
+        Ok(self.try_query(range)?.map_or(0, |(_, sum)| sum))
+    }
+
+    //None if the range is empty: the identity of MinMax is (i64::MAX, i64::MIN), which is no i32.
+    fn try_query(&self, range: impl RangeBounds<usize>) -> Result<Option<Node>, RangeError> {
+        let range = to_half_open(range, self.len())?;
+        if range.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.tree.query(range)))
+    }
+
+    //Sets a[i] = new_val for every i in the range, an empty range changes nothing.
+    //Panics if the range is reversed or out of bounds.
+    pub fn update(&mut self, range: impl RangeBounds<usize>, new_val: i32) {
+        self.try_update(range, new_val)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_update(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        new_val: i32,
+    ) -> Result<(), RangeError> {
+        self.tree.try_update(range, Assign(new_val as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use segment_tree::rng::Rng;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = Rng::new(41);
+        for n in 1..40 {
+            let mut naive: Vec<i32> = (0..n).map(|_| rng.next_u64() as i32).collect();
+            let mut tree = SegmentTree::new(&naive);
+
+            for _ in 0..200 {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let range = a.min(b)..=a.max(b);
+                match rng.below(4) {
+                    0 => {
+                        let value = rng.next_u64() as i32;
+                        tree.update(range.clone(), value);
+                        naive[range].iter_mut().for_each(|x| *x = value);
+                    }
+                    1 => assert_eq!(
+                        tree.max_query(range.clone()),
+                        naive[range].iter().max().copied()
+                    ),
+                    2 => assert_eq!(
+                        tree.min_query(range.clone()),
+                        naive[range].iter().min().copied()
+                    ),
+                    _ => assert_eq!(
+                        tree.sum_query(range.clone()),
+                        naive[range].iter().map(|&x| x as i64).sum::<i64>()
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_newer_assignment_wins() {
+        let mut tree = SegmentTree::new(&[9, 10, 11, 6, 5, 10, 11, 12, 13, 4]);
+        tree.update(0..5, 1);
+        tree.update(2..8, 20);
+        assert_eq!(tree.max_query(..), Some(20));
+        assert_eq!(tree.min_query(..), Some(1));
+        assert_eq!(tree.sum_query(..), 1 + 1 + 20 * 6 + 13 + 4);
+        //The smaller value is assigned too, the old tree only marked updates that lowered the node.
+        tree.update(.., 7);
+        assert_eq!(tree.max_query(3..=3), Some(7));
+        tree.update(4..=4, i32::MAX);
+        assert_eq!(tree.max_query(..), Some(i32::MAX));
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut tree = SegmentTree::new(&[1, 2, 3]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..2;
+        assert!(tree.try_update(reversed, 0).is_err());
+        assert!(tree.try_max_query(0..4).is_err());
+        assert!(tree.try_sum_query(4..).is_err());
+        assert_eq!(tree.sum_query(..), 6);
+    }
+
+    #[test]
+    fn test_empty_ranges() {
+        let mut tree = SegmentTree::new(&[i32::MIN, 2, i32::MAX]);
+        tree.update(3..3, 0);
+        assert_eq!(tree.max_query(1..1), None);
+        assert_eq!(tree.min_query(3..), None);
+        assert_eq!(tree.sum_query(..0), 0);
+        assert_eq!(tree.min_query(..), Some(i32::MIN));
+        assert_eq!(tree.max_query(..), Some(i32::MAX));
+
+        let mut empty = SegmentTree::new(&[]);
+        empty.update(.., 1);
+        assert_eq!(empty.max_query(..), None);
+        assert_eq!(empty.sum_query(..), 0);
+    }
+}
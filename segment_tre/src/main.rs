@@ -1,89 +1,79 @@
-#[derive(Debug)]
-struct SegmentTree {
-    size: usize,
-    tree: Vec<i32>,
-    marked: Vec<bool>,
-}
+use segment_tre::SegmentTree;
 
-impl SegmentTree {
-    fn new(array: &[i32]) -> Self {
-        let tree = vec![0; 4 * array.len()];
-        let marked = vec![false; 4 * array.len()];
-        SegmentTree {
-            size: array.len(),
-            tree,
-            marked,
-        }
-    }
+#[allow(clippy::items_after_test_module)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use segment_tree::range::one_based;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
 
-    fn build_tree(&mut self, array: &[i32], v: usize, left: usize, right: usize) {
-        if left == right {
-            self.tree[v] = array[left];
-        } else {
-            let mid = (left + right) / 2;
-            self.build_tree(&array, v * 2, left, mid);
-            self.build_tree(&array, v * 2 + 1, mid + 1, right);
-            self.tree[v] = self.tree[v * 2].max(self.tree[v * 2 + 1]);
-        }
-    }
+    //Each input has "n m", the array and m lines: "0 l r t" assigns t to [l, r], "1 l r", "2 l r" and "3 l r"
+    //ask max, min and sum of [l, r]. Ranges are 1 indexed and inclusive, the output has one line per query.
+    #[test]
+    fn run_tests() {
+        let directory_path = "src/Testset_assign/";
 
-    //TODO this is 0 indexed and should be tested.
-    fn max_query(
-        &mut self,
-        v: usize,
-        seg_l: usize,
-        seg_r: usize,
-        query_l: usize,
-        query_r: usize,
-    ) -> i32 {
-        if query_l > query_r {
-            return i32::MIN; // Return the minimum value for an empty range
-        }
-        if query_l == seg_l && query_r == seg_r {
-            return self.tree[v];
-        }
-        self.push(v);
-        let mid = (seg_l + seg_r) / 2;
-        return self
-            .max_query(v * 2, seg_l, mid, query_l, query_r.min(mid))
-            .max(self.max_query(v * 2 + 1, mid + 1, seg_r, query_l.max(mid + 1), query_r));
-    }
+        for i in 0..=7 {
+            let input_filename = format! {"input{}.txt", i};
+            let output_filename = format! {"output{}.txt", i};
 
-    fn push(&mut self, v: usize) {
-        if self.marked[v] {
-            self.tree[v * 2] = self.tree[v];
-            self.tree[v * 2 + 1] = self.tree[v];
-            self.marked[v * 2] = true;
-            self.marked[v * 2 + 1] = true;
-            self.marked[v] = false;
-        }
-    }
+            let input_full_path = PathBuf::from(directory_path).join(Path::new(&input_filename));
+            let output_full_path = PathBuf::from(directory_path).join(Path::new(&output_filename));
 
-    fn update(&mut self, v: usize, tl: usize, tr: usize, l: usize, r: usize, new_val: i32) {
-        if l > r {
-            return;
-        }
+            let input_contents =
+                fs::read_to_string(input_full_path).expect("Failed to open the test file.");
+            let output_contents =
+                fs::read_to_string(output_full_path).expect("Failed to open the test file.");
+
+            let output_array: Vec<i64> = output_contents
+                .lines()
+                .map(|s| s.parse::<i64>().unwrap())
+                .collect();
+
+            let all_input_values: Vec<Vec<i64>> = input_contents
+                .lines()
+                .map(|line| {
+                    line.split_whitespace()
+                        .map(|s| s.parse::<i64>().unwrap())
+                        .collect()
+                })
+                .collect();
+
+            let input_values: Vec<i32> = all_input_values[1].iter().map(|&x| x as i32).collect();
+            let mut tree = SegmentTree::new(&input_values);
+            let mut output_index = 0;
 
-        if l == tl && tr == r {
-            if self.tree[v].min(new_val) == new_val {
-                self.tree[v] = self.tree[v].min(new_val);
-                self.marked[v] = true;
+            for line in all_input_values.iter().skip(2) {
+                let range = one_based(line[1] as usize, line[2] as usize);
+                let result = match line[0] {
+                    0 => {
+                        tree.update(range, line[3] as i32);
+                        continue;
+                    }
+                    1 => tree.max_query(range).map(i64::from),
+                    2 => tree.min_query(range).map(i64::from),
+                    _ => Some(tree.sum_query(range)),
+                };
+                assert_eq!(
+                    result,
+                    Some(output_array[output_index]),
+                    "query {} of {}",
+                    output_index,
+                    input_filename
+                );
+                output_index += 1;
             }
-        } else {
-            self.push(v);
-            let mid = (tl + tr) / 2;
-            self.update(v * 2, tl, mid, l, r.max(mid), new_val);
-            self.update(v * 2 + 1, mid + 1, tr, r.max(mid + 1), r, new_val);
+            assert_eq!(output_index, output_array.len());
         }
     }
 }
 
 fn main() {
     let mut tree = SegmentTree::new(&[9, 10, 11, 6, 5, 10, 11, 12, 13, 4]);
-    tree.build_tree(&[9, 10, 11, 6, 5, 10, 11, 12, 13, 4], 1, 0, tree.size - 1);
-    println!("{}", tree.max_query(1, 0, tree.size - 1, 0, 1));
-    tree.update(1, 0, tree.size - 1, 0, 4, 1);
-    //println!("tree post update {:?}", tree.marked);
-    println!("RESULT {}", tree.max_query(1, 0, tree.size - 1, 0, 3));
-    println!("tree post update {:?}", tree.tree);
+    println!("{:?}", tree.max_query(0..2));
+    tree.update(0..5, 1);
+    println!("max {:?}", tree.max_query(0..4));
+    println!("min {:?} sum {}", tree.min_query(..), tree.sum_query(..));
 }
@@ -0,0 +1,91 @@
+//Memory and speed of LazySegmentTree on sizes just above and just below a power of two,
+//the worst and the best case of a layout that rounds the size up to a power of two.
+//cargo bench --bench layout [-- <operations>]
+
+use segment_tree::lazy::LazySegmentTree;
+use segment_tree::ops::{Add, ChMin, MaxCount, MaxInfo, MinMax};
+use segment_tree::rng::Rng;
+use std::hint::black_box;
+use std::time::Instant;
+
+enum Op {
+    Update(usize, usize, i64),
+    Query(usize, usize),
+}
+
+fn random_ops(size: usize, count: usize, rng: &mut Rng) -> Vec<Op> {
+    (0..count)
+        .map(|_| {
+            let a = rng.below(size);
+            let b = rng.below(size);
+            let (start, end) = (a.min(b), a.max(b) + 1);
+            if rng.below(2) == 0 {
+                Op::Update(start, end, rng.below(1 << 30) as i64)
+            } else {
+                Op::Query(start, end)
+            }
+        })
+        .collect()
+}
+
+fn report(name: &str, size: usize, bytes: usize, ops: usize, seconds: f64) {
+    println!(
+        "{:<16} n = {:>9} {:>8.2} bytes/element {:>14.0} ops/s",
+        name,
+        size,
+        bytes as f64 / size as f64,
+        ops as f64 / seconds
+    );
+}
+
+fn main() {
+    //cargo bench passes --bench to the binary, only the numeric arguments are ours.
+    let count = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(1 << 20);
+
+    let mut rng = Rng::new(43);
+    for size in [(1 << 16) + 1, 1 << 16, (1 << 22) + 1, 1 << 22] {
+        let array: Vec<i64> = (0..size).map(|_| rng.below(1 << 30) as i64).collect();
+        let ops = random_ops(size, count, &mut rng);
+
+        let leaves: Vec<(i64, i64)> = array.iter().map(|&x| (x, x)).collect();
+        let mut tree = LazySegmentTree::<MinMax<i64>, Add<i64>>::new(&leaves);
+        let start = Instant::now();
+        let mut checksum = 0i64;
+        for op in &ops {
+            match *op {
+                Op::Update(l, r, t) => tree.update(l..r, Add(t - (1 << 29))),
+                Op::Query(l, r) => checksum = checksum.wrapping_add(tree.query(l..r).1),
+            }
+        }
+        report(
+            "minmax add",
+            size,
+            tree.memory_bytes(),
+            count,
+            start.elapsed().as_secs_f64(),
+        );
+        black_box(checksum);
+
+        let leaves: Vec<MaxInfo<i64>> = array.iter().map(|&x| MaxInfo::leaf(x)).collect();
+        let mut tree = LazySegmentTree::<MaxCount<i64>, ChMin<i64>>::new(&leaves);
+        let start = Instant::now();
+        let mut checksum = 0usize;
+        for op in &ops {
+            match *op {
+                Op::Update(l, r, t) => tree.update(l..r, ChMin(t)),
+                Op::Query(l, r) => checksum = checksum.wrapping_add(tree.query(l..r).count),
+            }
+        }
+        report(
+            "maxcount chmin",
+            size,
+            tree.memory_bytes(),
+            count,
+            start.elapsed().as_secs_f64(),
+        );
+        black_box(checksum);
+    }
+}
@@ -0,0 +1,122 @@
+//Build time, memory and query speed of WaveletTree and MergeSortTree on inputs in the format of
+//Testset_handson2_2324_p1: "n m", the n values, then m lines "0 l r t" or "1 l r" with 1 based ranges.
+//Every line gives a range, the static trees ignore the updates and answer the median and the count below t
+//(for a query line, below the value at the start of its range).
+//cargo bench --bench order [-- <input file>]
+//Without a file the input is generated in the same format with n = m = 2^20.
+
+use segment_tree::merge_sort::MergeSortTree;
+use segment_tree::range::one_based;
+use segment_tree::rng::Rng;
+use segment_tree::wavelet::WaveletTree;
+use std::fmt::Write;
+use std::hint::black_box;
+use std::ops::Range;
+use std::time::Instant;
+
+fn generate(size: usize, count: usize, rng: &mut Rng) -> String {
+    let mut input = format!("{} {}\n", size, count);
+    let values: Vec<String> = (0..size).map(|_| rng.below(1 << 30).to_string()).collect();
+    input.push_str(&values.join(" "));
+    input.push('\n');
+    for _ in 0..count {
+        let a = 1 + rng.below(size);
+        let b = 1 + rng.below(size);
+        if rng.below(2) == 0 {
+            writeln!(input, "0 {} {} {}", a.min(b), a.max(b), rng.below(1 << 30)).unwrap();
+        } else {
+            writeln!(input, "1 {} {}", a.min(b), a.max(b)).unwrap();
+        }
+    }
+    input
+}
+
+//The values and, for the m lines after them, the range and the bound of the count. An update gives its value,
+//a query the value of the array at the start of its range, so that the count is not always 0.
+//Only the m lines of the header are read, anything after them (a trailing blank line) is ignored.
+fn parse(input: &str) -> (Vec<i32>, Vec<(Range<usize>, i32)>) {
+    let mut lines = input.lines();
+    let header: Vec<usize> = lines
+        .next()
+        .unwrap()
+        .split_whitespace()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let values: Vec<i32> = lines
+        .next()
+        .unwrap()
+        .split_whitespace()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let queries = lines
+        .take(header[1])
+        .map(|line| {
+            let line: Vec<usize> = line
+                .split_whitespace()
+                .map(|s| s.parse().unwrap())
+                .collect();
+            let bound = line.get(3).map_or(values[line[1] - 1], |&t| t as i32);
+            (one_based(line[1], line[2]), bound)
+        })
+        .collect();
+    (values, queries)
+}
+
+fn report(name: &str, size: usize, build: f64, bytes: usize, queries: usize, seconds: f64) {
+    println!(
+        "{:<12} n = {:>8} build {:>8.3} s {:>8.2} bytes/element {:>12.0} queries/s",
+        name,
+        size,
+        build,
+        bytes as f64 / size as f64,
+        queries as f64 / seconds
+    );
+}
+
+fn main() {
+    //cargo bench passes --bench to the binary, the first other argument is the input file.
+    let input = match std::env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(path) => std::fs::read_to_string(path).expect("Failed to open the input file."),
+        None => generate(1 << 20, 1 << 20, &mut Rng::new(50)),
+    };
+    let (values, queries) = parse(&input);
+    let size = values.len();
+
+    let start = Instant::now();
+    let wavelet = WaveletTree::new(&values);
+    let build = start.elapsed().as_secs_f64();
+    let start = Instant::now();
+    let mut checksum = 0usize;
+    for (range, bound) in &queries {
+        checksum = checksum.wrapping_add(wavelet.median(range.clone()).unwrap() as usize);
+        checksum = checksum.wrapping_add(wavelet.count_less(range.clone(), *bound));
+    }
+    report(
+        "wavelet",
+        size,
+        build,
+        wavelet.memory_bytes(),
+        queries.len(),
+        start.elapsed().as_secs_f64(),
+    );
+    black_box(checksum);
+
+    let start = Instant::now();
+    let merge_sort = MergeSortTree::new(&values);
+    let build = start.elapsed().as_secs_f64();
+    let start = Instant::now();
+    let mut checksum = 0usize;
+    for (range, bound) in &queries {
+        checksum = checksum.wrapping_add(merge_sort.median(range.clone()).unwrap() as usize);
+        checksum = checksum.wrapping_add(merge_sort.count_less(range.clone(), *bound));
+    }
+    report(
+        "merge sort",
+        size,
+        build,
+        merge_sort.memory_bytes(),
+        queries.len(),
+        start.elapsed().as_secs_f64(),
+    );
+    black_box(checksum);
+}
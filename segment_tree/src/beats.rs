@@ -0,0 +1,513 @@
+//Segment Tree Beats (Ji Ruyi's "Ji driver" segment tree).
+//Range chmin, range chmax and range add updates with range sum, max and min queries.
+//Every node keeps the maximum, the strict second maximum and how many times the maximum appears (and the same
+//for the minimum). A chmin with t stops at a node when second max < t < max: only the elements equal to the max
+//change, so the sum is updated with the count. Otherwise the update goes down, and the potential argument of the
+//paper bounds the total work to amortised O(log² n) per operation.
+
+use crate::lazy::right_child;
+use crate::range::{to_half_open, RangeError};
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    sum: i64,
+    max: i64,
+    second_max: Option<i64>,
+    max_count: usize,
+    min: i64,
+    second_min: Option<i64>,
+    min_count: usize,
+}
+
+impl Node {
+    fn leaf(value: i64) -> Self {
+        Node {
+            sum: value,
+            max: value,
+            second_max: None,
+            max_count: 1,
+            min: value,
+            second_min: None,
+            min_count: 1,
+        }
+    }
+
+    fn merge(left: &Node, right: &Node) -> Node {
+        let max = left.max.max(right.max);
+        let min = left.min.min(right.min);
+
+        //The strict second max is the largest value smaller than max among the two max and the two second max.
+        let below_max = |node: &Node| {
+            if node.max < max {
+                Some(node.max)
+            } else {
+                node.second_max
+            }
+        };
+        let above_min = |node: &Node| {
+            if node.min > min {
+                Some(node.min)
+            } else {
+                node.second_min
+            }
+        };
+        let count = |value: i64, count: usize, target: i64| if value == target { count } else { 0 };
+
+        Node {
+            sum: left.sum + right.sum,
+            max,
+            second_max: below_max(left).max(below_max(right)),
+            max_count: count(left.max, left.max_count, max)
+                + count(right.max, right.max_count, max),
+            min,
+            second_min: match (above_min(left), above_min(right)) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+            min_count: count(left.min, left.min_count, min)
+                + count(right.min, right.min_count, min),
+        }
+    }
+
+    //Adds value to every one of the len elements.
+    fn add(&mut self, len: usize, value: i64) {
+        self.sum += value * len as i64;
+        self.max += value;
+        self.second_max = self.second_max.map(|second| second + value);
+        self.min += value;
+        self.second_min = self.second_min.map(|second| second + value);
+    }
+
+    //Lowers the max to value, only valid when second max < value < max.
+    fn chmin(&mut self, value: i64) {
+        self.sum -= (self.max - value) * self.max_count as i64;
+        if self.min == self.max {
+            self.min = value;
+        } else if self.second_min == Some(self.max) {
+            self.second_min = Some(value);
+        }
+        self.max = value;
+    }
+
+    //Raises the min to value, only valid when min < value < second min.
+    fn chmax(&mut self, value: i64) {
+        self.sum += (value - self.min) * self.min_count as i64;
+        if self.max == self.min {
+            self.max = value;
+        } else if self.second_max == Some(self.min) {
+            self.second_max = Some(value);
+        }
+        self.min = value;
+    }
+
+    //What a push makes of a child with len elements: the pending addition of the parent,
+    //then the max and min clamped to the ones of the parent.
+    fn under(mut self, len: usize, add: i64, parent: &Node) -> Node {
+        if add != 0 {
+            self.add(len, add);
+        }
+        if self.max > parent.max {
+            self.chmin(parent.max);
+        }
+        if self.min < parent.min {
+            self.chmax(parent.min);
+        }
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct BeatsTree {
+    size: usize,
+    tree: Vec<Node>,
+    //Pending addition for the children, the chmin and chmax tags are implicit in the max and min of the node.
+    //Same compact layout of LazySegmentTree: only the internal nodes have a tag, the one splitting after mid is lazy_add[mid].
+    lazy_add: Vec<i64>,
+}
+
+impl BeatsTree {
+    //Builds the tree on array, which may be empty. The sums of the ranges must fit in an i64.
+    pub fn new(array: &[i64]) -> Self {
+        let mut beats = BeatsTree {
+            size: array.len(),
+            tree: vec![Node::leaf(0); (2 * array.len()).saturating_sub(1)],
+            lazy_add: vec![0; array.len().saturating_sub(1)],
+        };
+        if !array.is_empty() {
+            beats.build(array, 0, 0, array.len() - 1);
+        }
+        beats
+    }
+
+    fn build(&mut self, array: &[i64], v: usize, low: usize, high: usize) {
+        if low == high {
+            self.tree[v] = Node::leaf(array[low]);
+            return;
+        }
+        let mid = (low + high) / 2;
+        self.build(array, v + 1, low, mid);
+        self.build(array, right_child(v, low, mid), mid + 1, high);
+        self.pull(v, low, mid);
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    //Sets a[i] = min(a[i], value) for every i in range.
+    //Panics if range is reversed or out of bounds, the same holds for the other updates and queries.
+    //An empty range is valid: the updates do nothing on it.
+    pub fn chmin(&mut self, range: impl RangeBounds<usize>, value: i64) {
+        self.try_chmin(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_chmin(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<(), RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start < end {
+            self.chmin_rec(0, 0, self.size - 1, start, end - 1, value);
+        }
+        Ok(())
+    }
+
+    //Sets a[i] = max(a[i], value) for every i in range.
+    pub fn chmax(&mut self, range: impl RangeBounds<usize>, value: i64) {
+        self.try_chmax(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_chmax(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<(), RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start < end {
+            self.chmax_rec(0, 0, self.size - 1, start, end - 1, value);
+        }
+        Ok(())
+    }
+
+    //Adds value to every element in range.
+    pub fn add(&mut self, range: impl RangeBounds<usize>, value: i64) {
+        self.try_add(range, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_add(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        value: i64,
+    ) -> Result<(), RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start < end {
+            self.add_rec(0, 0, self.size - 1, start, end - 1, value);
+        }
+        Ok(())
+    }
+
+    //Sum of the elements in range, 0 if it is empty.
+    pub fn sum(&self, range: impl RangeBounds<usize>) -> i64 {
+        self.try_sum(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_sum(&self, range: impl RangeBounds<usize>) -> Result<i64, RangeError> {
+        Ok(self.query(range)?.map_or(0, |node| node.sum))
+    }
+
+    //Max of the elements in range, None if it is empty.
+    pub fn max(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.try_max(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_max(&self, range: impl RangeBounds<usize>) -> Result<Option<i64>, RangeError> {
+        Ok(self.query(range)?.map(|node| node.max))
+    }
+
+    //Min of the elements in range, None if it is empty.
+    pub fn min(&self, range: impl RangeBounds<usize>) -> Option<i64> {
+        self.try_min(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_min(&self, range: impl RangeBounds<usize>) -> Result<Option<i64>, RangeError> {
+        Ok(self.query(range)?.map(|node| node.min))
+    }
+
+    //None for an empty range, there is no node for it.
+    //The query does not push: the pending additions and clamps of the ancestors are applied to a copy of every node
+    //on the way down, so it takes &self and is still O(log n).
+    fn query(&self, range: impl RangeBounds<usize>) -> Result<Option<Node>, RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start == end {
+            return Ok(None);
+        }
+        Ok(Some(self.query_rec(
+            0,
+            0,
+            self.size - 1,
+            start,
+            end - 1,
+            self.tree[0],
+            0,
+        )))
+    }
+
+    fn chmin_rec(
+        &mut self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        value: i64,
+    ) {
+        if right < low || high < left || self.tree[v].max <= value {
+            return;
+        }
+        if left <= low
+            && high <= right
+            && self.tree[v].second_max.is_none_or(|second| second < value)
+        {
+            self.tree[v].chmin(value);
+            return;
+        }
+        self.push(v, low, high);
+        let mid = (low + high) / 2;
+        self.chmin_rec(v + 1, low, mid, left, right, value);
+        self.chmin_rec(right_child(v, low, mid), mid + 1, high, left, right, value);
+        self.pull(v, low, mid);
+    }
+
+    fn chmax_rec(
+        &mut self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        value: i64,
+    ) {
+        if right < low || high < left || self.tree[v].min >= value {
+            return;
+        }
+        if left <= low
+            && high <= right
+            && self.tree[v].second_min.is_none_or(|second| second > value)
+        {
+            self.tree[v].chmax(value);
+            return;
+        }
+        self.push(v, low, high);
+        let mid = (low + high) / 2;
+        self.chmax_rec(v + 1, low, mid, left, right, value);
+        self.chmax_rec(right_child(v, low, mid), mid + 1, high, left, right, value);
+        self.pull(v, low, mid);
+    }
+
+    fn add_rec(
+        &mut self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        value: i64,
+    ) {
+        if right < low || high < left {
+            return;
+        }
+        if left <= low && high <= right {
+            self.apply_add(v, low, high, value);
+            return;
+        }
+        self.push(v, low, high);
+        let mid = (low + high) / 2;
+        self.add_rec(v + 1, low, mid, left, right, value);
+        self.add_rec(right_child(v, low, mid), mid + 1, high, left, right, value);
+        self.pull(v, low, mid);
+    }
+
+    //node is the value v would have after pushing every ancestor, add the addition those pushes would give to v.
+    #[allow(clippy::too_many_arguments)]
+    fn query_rec(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        node: Node,
+        add: i64,
+    ) -> Node {
+        if left <= low && high <= right {
+            return node;
+        }
+        let mid = (low + high) / 2;
+        let add = add + self.lazy_add[mid];
+        let query_child = |child: usize, low: usize, high: usize| {
+            let child_node = self.tree[child].under(high - low + 1, add, &node);
+            self.query_rec(child, low, high, left, right, child_node, add)
+        };
+        if right <= mid {
+            query_child(v + 1, low, mid)
+        } else if left > mid {
+            query_child(right_child(v, low, mid), mid + 1, high)
+        } else {
+            Node::merge(
+                &query_child(v + 1, low, mid),
+                &query_child(right_child(v, low, mid), mid + 1, high),
+            )
+        }
+    }
+
+    fn apply_add(&mut self, v: usize, low: usize, high: usize, value: i64) {
+        self.tree[v].add(high - low + 1, value);
+        if low != high {
+            self.lazy_add[(low + high) / 2] += value;
+        }
+    }
+
+    //Pushes the pending addition, then the children max and min are clamped to the ones of the parent.
+    fn push(&mut self, v: usize, low: usize, high: usize) {
+        let mid = (low + high) / 2;
+        let add = std::mem::take(&mut self.lazy_add[mid]);
+        let parent = self.tree[v];
+
+        for (child, low, high) in [(v + 1, low, mid), (right_child(v, low, mid), mid + 1, high)] {
+            self.tree[child] = self.tree[child].under(high - low + 1, add, &parent);
+            if low != high {
+                self.lazy_add[(low + high) / 2] += add;
+            }
+        }
+    }
+
+    fn pull(&mut self, v: usize, low: usize, mid: usize) {
+        self.tree[v] = Node::merge(&self.tree[v + 1], &self.tree[right_child(v, low, mid)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = Rng::new(32);
+        for _ in 0..40 {
+            let n = 1 + rng.below(80);
+            let mut naive: Vec<i64> = (0..n).map(|_| rng.below(201) as i64 - 100).collect();
+            let mut beats = BeatsTree::new(&naive);
+
+            for _ in 0..400 {
+                let mut left = rng.below(n);
+                let mut right = rng.below(n);
+                if left > right {
+                    std::mem::swap(&mut left, &mut right);
+                }
+                let value = rng.below(201) as i64 - 100;
+                let range = &mut naive[left..=right];
+
+                match rng.below(6) {
+                    0 => {
+                        beats.chmin(left..=right, value);
+                        range.iter_mut().for_each(|x| *x = (*x).min(value));
+                    }
+                    1 => {
+                        beats.chmax(left..=right, value);
+                        range.iter_mut().for_each(|x| *x = (*x).max(value));
+                    }
+                    2 => {
+                        beats.add(left..=right, value / 10);
+                        range.iter_mut().for_each(|x| *x += value / 10);
+                    }
+                    3 => assert_eq!(beats.sum(left..=right), range.iter().sum::<i64>()),
+                    4 => assert_eq!(beats.max(left..=right), range.iter().max().copied()),
+                    _ => assert_eq!(beats.min(left..=right), range.iter().min().copied()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut beats = BeatsTree::new(&[]);
+        assert!(beats.is_empty());
+        beats.chmin(.., 3);
+        beats.chmax(0..0, 3);
+        beats.add(.., 1);
+        assert_eq!(beats.sum(..), 0);
+        assert_eq!(beats.max(..), None);
+        assert_eq!(beats.min(..), None);
+        assert!(beats.try_add(..1, 1).is_err());
+    }
+
+    #[test]
+    fn test_sum_under_chmin() {
+        //Capacity clamping: the sum of the range after every element is clamped to a capacity.
+        let mut beats = BeatsTree::new(&[5, 1, 4, 3, 2, 7, 4]);
+        assert_eq!(beats.sum(..), 26);
+        beats.chmin(.., 4);
+        assert_eq!(beats.sum(..), 22);
+        beats.chmin(2..5, 2);
+        assert_eq!(beats.sum(..), 19);
+        assert_eq!(beats.max(..), Some(4));
+        assert_eq!(beats.min(..), Some(1));
+        beats.add(.., 10);
+        assert_eq!(beats.sum(..), 89);
+        beats.chmax(.., 13);
+        assert_eq!(beats.min(..), Some(13));
+        assert_eq!(beats.sum(..1), 14);
+    }
+
+    //The queries read the pending tags without pushing them, the tree is left as it was.
+    #[test]
+    fn test_queries_do_not_push() {
+        let mut beats = BeatsTree::new(&[5, 1, 4, 3, 2, 7, 4, 6]);
+        beats.add(.., 3);
+        beats.chmin(.., 8);
+        beats.chmax(2..6, 6);
+        let lazy_add = beats.lazy_add.clone();
+        let beats = &beats;
+        assert_eq!(beats.sum(1..2), 4);
+        assert_eq!(beats.max(3..), Some(8));
+        assert_eq!(beats.min(..5), Some(4));
+        assert_eq!(beats.sum(..), 8 + 4 + 7 + 6 + 6 + 8 + 7 + 8);
+        assert_eq!(beats.lazy_add, lazy_add);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds() {
+        let beats = BeatsTree::new(&[1, 2, 3]);
+        beats.sum(1..=3);
+    }
+
+    #[test]
+    fn test_try_variants() {
+        let mut beats = BeatsTree::new(&[1, 2, 3]);
+        assert_eq!(beats.try_sum(1..3), Ok(5));
+        assert!(beats.try_max(4..).is_err());
+        assert!(beats.try_add(0..4, 1).is_err());
+        assert_eq!(beats.try_min(..), Ok(Some(1)));
+
+        //Empty ranges: nothing to update, no max or min and a sum of 0.
+        beats.chmin(0..0, i64::MIN);
+        assert_eq!(beats.try_max(3..), Ok(None));
+        assert_eq!(beats.min(1..1), None);
+        assert_eq!(beats.sum(2..2), 0);
+        assert_eq!(beats.min(..), Some(1));
+    }
+}
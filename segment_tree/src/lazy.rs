@@ -0,0 +1,1575 @@
+use crate::range::{to_half_open, RangeError};
+use std::fmt;
+use std::ops::{Range, RangeBounds};
+use std::thread;
+
+//The values stored in the tree. combine must be associative and identity must be its neutral element,
+//it is the result of a query on an empty range.
+pub trait Monoid {
+    type Value: Clone;
+
+    fn identity() -> Self::Value;
+    fn combine(left: &Self::Value, right: &Self::Value) -> Self::Value;
+}
+
+//The range updates, applied lazily to the values of a Monoid.
+//apply must distribute over combine: applying the action to the combination of len elements
+//gives the same result of applying it to every element and then combining them.
+pub trait Action<M: Monoid>: Clone {
+    //Applies the action to the aggregated value of len consecutive elements.
+    fn apply(&self, value: &M::Value, len: usize) -> M::Value;
+
+    //Returns the action that applies earlier first and then self.
+    fn compose(&self, earlier: &Self) -> Self;
+
+    //Returns true if the action cannot be applied to the aggregated value of a subtree, because the result
+    //depends on elements the aggregate does not describe. The tree then applies it to the two children and
+    //combines them again, as in Segment Tree Beats. It is never asked for a single element.
+    fn breaks_on(&self, _value: &M::Value) -> bool {
+        false
+    }
+}
+
+//Generic segment tree with lazy propagation.
+//The nodes are stored in 2n - 1 slots in preorder: the root is 0, the left child of the node v covering
+//[low, high] is v + 1 and its right child comes after the 2(mid - low + 1) - 1 nodes of the left subtree.
+//The value of a node is always up to date, its lazy tag is the action still to be pushed to its children.
+//The tree is built on capacity leaves, the first len hold the elements and the others the identity,
+//so push only rebuilds when the storage is full.
+pub struct LazySegmentTree<M: Monoid, A: Action<M>> {
+    size: usize,
+    //Number of leaves, at least size. The updates never reach the leaves past size, they keep the identity.
+    capacity: usize,
+    tree: Vec<M::Value>,
+    //Only the n - 1 internal nodes have a tag. Every internal node splits its range after a different mid,
+    //so the tag of the node is lazy[mid].
+    lazy: Vec<Option<A>>,
+    //Old contents of every slot written since the first checkpoint, the last change on top. Empty and not
+    //recording until checkpoint is called.
+    journal: Vec<Change<M::Value, A>>,
+    recording: bool,
+}
+
+//A slot of the tree before it was overwritten.
+enum Change<V, A> {
+    Node(usize, V),
+    Tag(usize, Option<A>),
+    Size(usize),
+    //The whole storage before a growth.
+    Grow {
+        capacity: usize,
+        tree: Vec<V>,
+        lazy: Vec<Option<A>>,
+    },
+}
+
+//A state of a LazySegmentTree that rollback can go back to, see LazySegmentTree::checkpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+//Right child of the node v covering [low, high], where mid = (low + high) / 2. The left child is v + 1.
+pub(crate) fn right_child(v: usize, low: usize, mid: usize) -> usize {
+    v + 2 * (mid - low + 1)
+}
+
+impl<M: Monoid, A: Action<M>> LazySegmentTree<M, A> {
+    pub fn new(array: &[M::Value]) -> Self {
+        let mut segment_tree = Self {
+            size: 0,
+            capacity: 0,
+            tree: Vec::new(),
+            lazy: Vec::new(),
+            journal: Vec::new(),
+            recording: false,
+        };
+        segment_tree.rebuild_from(array);
+        segment_tree
+    }
+
+    //Replaces the elements with array, which may have a different length, and drops every pending tag. O(n).
+    //The buffers of the tree are reused: they only reallocate if array is longer than every array before it.
+    //The history is forgotten as in forget_history.
+    pub fn rebuild_from(&mut self, array: &[M::Value]) {
+        self.rebuild_with(array.len(), |i| array[i].clone());
+    }
+
+    //Same as rebuild_from on the array of length len whose element i is leaf(i),
+    //for the callers that would otherwise collect the values in a new Vec first.
+    pub fn rebuild_with(&mut self, len: usize, leaf: impl Fn(usize) -> M::Value) {
+        self.forget_history();
+        self.size = len;
+        self.fill(len, leaf);
+    }
+
+    //Builds the tree on capacity leaves in the current buffers, without touching the journal.
+    fn fill(&mut self, capacity: usize, leaf: impl Fn(usize) -> M::Value) {
+        self.capacity = capacity;
+        self.tree.clear();
+        self.tree
+            .resize((2 * capacity).saturating_sub(1), M::identity());
+        self.lazy.clear();
+        self.lazy.resize(capacity.saturating_sub(1), None);
+        if capacity > 0 {
+            let recording = std::mem::replace(&mut self.recording, false);
+            self.build(&leaf, 0, 0, capacity - 1);
+            self.recording = recording;
+        }
+    }
+
+    fn build(&mut self, leaf: &impl Fn(usize) -> M::Value, v: usize, low: usize, high: usize) {
+        if low == high {
+            self.tree[v] = leaf(low);
+            return;
+        }
+        let mid = (low + high) / 2;
+        self.build(leaf, v + 1, low, mid);
+        self.build(leaf, right_child(v, low, mid), mid + 1, high);
+        self.pull(v, low, mid);
+    }
+
+    //Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    //Returns the number of elements the tree holds before push has to grow the storage.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    //Sets the element at index to value, O(log n).
+    //Panics if index is out of bounds.
+    pub fn set(&mut self, index: usize, value: M::Value) {
+        self.try_set(index, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_set(&mut self, index: usize, value: M::Value) -> Result<(), RangeError> {
+        to_half_open(index..=index, self.size)?;
+        self.replace_rec(0, 0, self.capacity - 1, index, value);
+        Ok(())
+    }
+
+    //Appends value after the last element, amortised O(log n).
+    //When the storage is full its capacity doubles: the elements are read with their pending updates applied
+    //and the tree is built again on them, in O(n) once every n pushes.
+    pub fn push(&mut self, value: M::Value) {
+        if self.size == self.capacity {
+            self.grow((2 * self.capacity).max(1));
+        }
+        self.set_size(self.size + 1);
+        self.set(self.size - 1, value);
+    }
+
+    //Removes the last element and returns it, None if the tree is empty. O(log n), the capacity stays the same.
+    pub fn pop(&mut self) -> Option<M::Value> {
+        if self.size == 0 {
+            return None;
+        }
+        let last = self.replace_rec(0, 0, self.capacity - 1, self.size - 1, M::identity());
+        self.set_size(self.size - 1);
+        Some(last)
+    }
+
+    //Changes the number of elements to len, removing the last ones or appending copies of fill.
+    //Amortised O(log n) for every element removed or added, the storage grows at most once.
+    pub fn resize(&mut self, len: usize, fill: M::Value) {
+        while self.size > len {
+            self.pop();
+        }
+        if len > self.capacity {
+            self.grow(len.max(2 * self.capacity));
+        }
+        while self.size < len {
+            self.push(fill.clone());
+        }
+    }
+
+    //Builds the tree again on more leaves, the elements first and the identity after them.
+    //While recording the old buffers go to the journal whole. The capacity at least doubles every time,
+    //so they take O(n) memory over all the growths.
+    fn grow(&mut self, capacity: usize) {
+        let values = self.to_vec();
+        if self.recording {
+            let old = Change::Grow {
+                capacity: self.capacity,
+                tree: std::mem::take(&mut self.tree),
+                lazy: std::mem::take(&mut self.lazy),
+            };
+            self.journal.push(old);
+        }
+        self.fill(capacity, |i| {
+            values.get(i).cloned().unwrap_or_else(M::identity)
+        });
+    }
+
+    fn set_size(&mut self, size: usize) {
+        let old = std::mem::replace(&mut self.size, size);
+        if self.recording {
+            self.journal.push(Change::Size(old));
+        }
+    }
+
+    //Puts value at index and returns the element it replaces, the tags on the path are pushed down first.
+    fn replace_rec(
+        &mut self,
+        v: usize,
+        low: usize,
+        high: usize,
+        index: usize,
+        value: M::Value,
+    ) -> M::Value {
+        if low == high {
+            let old = self.tree[v].clone();
+            self.set_node(v, value);
+            return old;
+        }
+        self.push_down(v, low, high);
+        let mid = (low + high) / 2;
+        let old = if index <= mid {
+            self.replace_rec(v + 1, low, mid, index, value)
+        } else {
+            self.replace_rec(right_child(v, low, mid), mid + 1, high, index, value)
+        };
+        self.pull(v, low, mid);
+        old
+    }
+
+    //Bytes taken by the node values and the tags, the memory of the tree apart from the two Vec headers.
+    pub fn memory_bytes(&self) -> usize {
+        self.tree.len() * std::mem::size_of::<M::Value>()
+            + self.lazy.len() * std::mem::size_of::<Option<A>>()
+    }
+
+    //Returns the elements in order with every pending update applied, in O(n) for the whole array.
+    //The tags are carried down as in the queries, so the tree is not modified.
+    pub fn leaves(&self) -> Leaves<'_, M, A> {
+        Leaves {
+            tree: self,
+            stack: if self.size > 0 {
+                vec![(0, 0, self.capacity - 1, None)]
+            } else {
+                Vec::new()
+            },
+            remaining: self.size,
+        }
+    }
+
+    //Returns the current array, the elements of leaves collected.
+    pub fn to_vec(&self) -> Vec<M::Value> {
+        self.leaves().collect()
+    }
+
+    //Drops every pending tag. The updates that have not been pushed yet are lost.
+    pub fn reset_lazy(&mut self) {
+        for mid in 0..self.lazy.len() {
+            self.take_tag(mid);
+        }
+    }
+
+    //Returns the current state, to go back to it later with rollback.
+    //From the first checkpoint on, every update records the slots it overwrites (the values of the nodes
+    //and the tags, pushes included), so it takes O(1) extra memory for each node it touches.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.recording = true;
+        Checkpoint(self.journal.len())
+    }
+
+    //Undoes every update since checkpoint, in time proportional to the slots they wrote.
+    //The checkpoints are a stack: going back to one invalidates every checkpoint taken after it,
+    //while it and the ones before it can still be used.
+    //Panics if the tree has already gone back past checkpoint, or if it was taken before a rebuild
+    //or a forget_history.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        assert!(
+            self.recording && checkpoint.0 <= self.journal.len(),
+            "Checkpoint no longer valid"
+        );
+        for change in self.journal.drain(checkpoint.0..).rev() {
+            match change {
+                Change::Node(v, value) => self.tree[v] = value,
+                Change::Tag(mid, tag) => self.lazy[mid] = tag,
+                Change::Size(size) => self.size = size,
+                Change::Grow {
+                    capacity,
+                    tree,
+                    lazy,
+                } => (self.capacity, self.tree, self.lazy) = (capacity, tree, lazy),
+            }
+        }
+    }
+
+    //Stops recording the updates and drops the history, every checkpoint taken so far becomes invalid.
+    pub fn forget_history(&mut self) {
+        self.journal.clear();
+        self.recording = false;
+    }
+
+    //Combines the elements in range, the identity if it is empty.
+    //Panics if range is reversed or out of bounds.
+    pub fn query(&self, range: impl RangeBounds<usize>) -> M::Value {
+        self.try_query(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<M::Value, RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start == end {
+            return Ok(M::identity());
+        }
+        Ok(self.query_rec(0, 0, self.capacity - 1, start, end - 1, &None))
+    }
+
+    //`pending` is the action of the ancestors of v that has not been pushed to v yet, see pending_below.
+    fn query_rec(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        pending: &Option<A>,
+    ) -> M::Value {
+        if right < low || high < left {
+            return M::identity();
+        }
+        if left <= low && high <= right {
+            return self.value(v, low, high, pending);
+        }
+
+        let mid = (low + high) / 2;
+        let below = self.pending_below(mid, pending);
+        let left_value = self.query_rec(v + 1, low, mid, left, right, &below);
+        let right_value =
+            self.query_rec(right_child(v, low, mid), mid + 1, high, left, right, &below);
+        M::combine(&left_value, &right_value)
+    }
+
+    //Answers the queries on the half-open ranges start..end of ranges in order, spread over one scoped thread
+    //per available core. The queries only read the tree, so the threads share it without locks.
+    //Panics if one of the ranges is reversed or out of bounds, before any query runs.
+    pub fn query_batch(&self, ranges: &[(usize, usize)]) -> Vec<M::Value>
+    where
+        M::Value: Send + Sync,
+        A: Sync,
+    {
+        self.try_query_batch(ranges)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_query_batch(&self, ranges: &[(usize, usize)]) -> Result<Vec<M::Value>, RangeError>
+    where
+        M::Value: Send + Sync,
+        A: Sync,
+    {
+        for &(start, end) in ranges {
+            to_half_open(start..end, self.size)?;
+        }
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk = ranges.len().div_ceil(threads).max(1);
+        Ok(thread::scope(|scope| {
+            let workers: Vec<_> = ranges
+                .chunks(chunk)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|&(start, end)| self.query(start..end))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        }))
+    }
+
+    //Applies action to every element in range.
+    //Panics if range is reversed or out of bounds.
+    pub fn update(&mut self, range: impl RangeBounds<usize>, action: A) {
+        self.try_update(range, action)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_update(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        action: A,
+    ) -> Result<(), RangeError> {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start < end {
+            self.update_rec(0, 0, self.capacity - 1, start, end - 1, &action);
+        }
+        Ok(())
+    }
+
+    fn update_rec(
+        &mut self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        action: &A,
+    ) {
+        if right < low || high < left {
+            return;
+        }
+        if left <= low && high <= right {
+            self.apply_node(v, low, high, action);
+            return;
+        }
+        self.push_down(v, low, high);
+
+        let mid = (low + high) / 2;
+        self.update_rec(v + 1, low, mid, left, right, action);
+        self.update_rec(right_child(v, low, mid), mid + 1, high, left, right, action);
+        self.pull(v, low, mid);
+    }
+
+    //Returns the first index in range whose element satisfies is_match.
+    //The search skips every subtree whose aggregated value does not satisfy may_contain, so may_contain
+    //must be true for every aggregate that includes an element satisfying is_match.
+    //Panics if range is reversed or out of bounds.
+    pub fn find_first<P, Q>(
+        &self,
+        range: impl RangeBounds<usize>,
+        may_contain: P,
+        is_match: Q,
+    ) -> Option<usize>
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        self.try_find_first(range, may_contain, is_match)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_find_first<P, Q>(
+        &self,
+        range: impl RangeBounds<usize>,
+        may_contain: P,
+        is_match: Q,
+    ) -> Result<Option<usize>, RangeError>
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start == end {
+            return Ok(None);
+        }
+        Ok(self.find_first_rec(
+            0,
+            0,
+            self.capacity - 1,
+            start,
+            end - 1,
+            &may_contain,
+            &is_match,
+            &None,
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn find_first_rec<P, Q>(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        may_contain: &P,
+        is_match: &Q,
+        pending: &Option<A>,
+    ) -> Option<usize>
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        if right < low || high < left {
+            return None;
+        }
+        let value = self.value(v, low, high, pending);
+        if !may_contain(&value) {
+            return None;
+        }
+        if low == high {
+            return is_match(&value).then_some(low);
+        }
+
+        let mid = (low + high) / 2;
+        let below = self.pending_below(mid, pending);
+        self.find_first_rec(v + 1, low, mid, left, right, may_contain, is_match, &below)
+            .or_else(|| {
+                self.find_first_rec(
+                    right_child(v, low, mid),
+                    mid + 1,
+                    high,
+                    left,
+                    right,
+                    may_contain,
+                    is_match,
+                    &below,
+                )
+            })
+    }
+
+    //Returns the last index in range whose element satisfies is_match, with the same pruning of find_first.
+    //Panics if range is reversed or out of bounds.
+    pub fn find_last<P, Q>(
+        &self,
+        range: impl RangeBounds<usize>,
+        may_contain: P,
+        is_match: Q,
+    ) -> Option<usize>
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        self.try_find_last(range, may_contain, is_match)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_find_last<P, Q>(
+        &self,
+        range: impl RangeBounds<usize>,
+        may_contain: P,
+        is_match: Q,
+    ) -> Result<Option<usize>, RangeError>
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start == end {
+            return Ok(None);
+        }
+        Ok(self.find_last_rec(
+            0,
+            0,
+            self.capacity - 1,
+            start,
+            end - 1,
+            &may_contain,
+            &is_match,
+            &None,
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn find_last_rec<P, Q>(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        may_contain: &P,
+        is_match: &Q,
+        pending: &Option<A>,
+    ) -> Option<usize>
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        if right < low || high < left {
+            return None;
+        }
+        let value = self.value(v, low, high, pending);
+        if !may_contain(&value) {
+            return None;
+        }
+        if low == high {
+            return is_match(&value).then_some(low);
+        }
+
+        let mid = (low + high) / 2;
+        let below = self.pending_below(mid, pending);
+        self.find_last_rec(
+            right_child(v, low, mid),
+            mid + 1,
+            high,
+            left,
+            right,
+            may_contain,
+            is_match,
+            &below,
+        )
+        .or_else(|| self.find_last_rec(v + 1, low, mid, left, right, may_contain, is_match, &below))
+    }
+
+    //Returns how many elements in range satisfy is_match, with the same pruning of find_first.
+    //Unlike the searches it does not stop at the first match, so it visits every subtree that satisfies may_contain.
+    //Panics if range is reversed or out of bounds.
+    pub fn count_matches<P, Q>(
+        &self,
+        range: impl RangeBounds<usize>,
+        may_contain: P,
+        is_match: Q,
+    ) -> usize
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        self.try_count_matches(range, may_contain, is_match)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_count_matches<P, Q>(
+        &self,
+        range: impl RangeBounds<usize>,
+        may_contain: P,
+        is_match: Q,
+    ) -> Result<usize, RangeError>
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        let Range { start, end } = to_half_open(range, self.size)?;
+        if start == end {
+            return Ok(0);
+        }
+        Ok(self.count_rec(
+            0,
+            0,
+            self.capacity - 1,
+            start,
+            end - 1,
+            &may_contain,
+            &is_match,
+            &None,
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn count_rec<P, Q>(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        left: usize,
+        right: usize,
+        may_contain: &P,
+        is_match: &Q,
+        pending: &Option<A>,
+    ) -> usize
+    where
+        P: Fn(&M::Value) -> bool,
+        Q: Fn(&M::Value) -> bool,
+    {
+        if right < low || high < left {
+            return 0;
+        }
+        let value = self.value(v, low, high, pending);
+        if !may_contain(&value) {
+            return 0;
+        }
+        if low == high {
+            return is_match(&value) as usize;
+        }
+
+        let mid = (low + high) / 2;
+        let below = self.pending_below(mid, pending);
+        self.count_rec(v + 1, low, mid, left, right, may_contain, is_match, &below)
+            + self.count_rec(
+                right_child(v, low, mid),
+                mid + 1,
+                high,
+                left,
+                right,
+                may_contain,
+                is_match,
+                &below,
+            )
+    }
+
+    //Binary search from start: returns the largest end such that pred holds for the combination of start..end.
+    //pred must be monotone (once it fails on a prefix it fails on every longer one) and must hold for the identity,
+    //so end == start is always valid. The tags met during the descent are accumulated, not pushed, as in find_first. O(log n).
+    //Panics if start is greater than the length.
+    pub fn max_right<P>(&self, start: usize, pred: P) -> usize
+    where
+        P: Fn(&M::Value) -> bool,
+    {
+        self.try_max_right(start, pred)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_max_right<P>(&self, start: usize, pred: P) -> Result<usize, RangeError>
+    where
+        P: Fn(&M::Value) -> bool,
+    {
+        if start > self.size {
+            return Err(RangeError::OutOfBounds {
+                end: start,
+                len: self.size,
+            });
+        }
+        if start == self.size {
+            return Ok(self.size);
+        }
+        let mut acc = M::identity();
+        Ok(self
+            .max_right_rec(0, 0, self.capacity - 1, start, &mut acc, &pred, &None)
+            .unwrap_or(self.size))
+    }
+
+    //Returns the first index where the prefix from start stops satisfying pred, acc is the combination of the prefix before v.
+    #[allow(clippy::too_many_arguments)]
+    fn max_right_rec<P>(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        start: usize,
+        acc: &mut M::Value,
+        pred: &P,
+        pending: &Option<A>,
+    ) -> Option<usize>
+    where
+        P: Fn(&M::Value) -> bool,
+    {
+        if high < start {
+            return None;
+        }
+        if start <= low {
+            let combined = M::combine(acc, &self.value(v, low, high, pending));
+            if pred(&combined) {
+                *acc = combined;
+                return None;
+            }
+            if low == high {
+                return Some(low);
+            }
+        }
+
+        let mid = (low + high) / 2;
+        let below = self.pending_below(mid, pending);
+        self.max_right_rec(v + 1, low, mid, start, acc, pred, &below)
+            .or_else(|| {
+                self.max_right_rec(
+                    right_child(v, low, mid),
+                    mid + 1,
+                    high,
+                    start,
+                    acc,
+                    pred,
+                    &below,
+                )
+            })
+    }
+
+    //Binary search towards the left from end: returns the smallest start such that pred holds for the
+    //combination of start..end. Same requirements of max_right on pred. O(log n).
+    //Panics if end is greater than the length.
+    pub fn min_left<P>(&self, end: usize, pred: P) -> usize
+    where
+        P: Fn(&M::Value) -> bool,
+    {
+        self.try_min_left(end, pred)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_min_left<P>(&self, end: usize, pred: P) -> Result<usize, RangeError>
+    where
+        P: Fn(&M::Value) -> bool,
+    {
+        if end > self.size {
+            return Err(RangeError::OutOfBounds {
+                end,
+                len: self.size,
+            });
+        }
+        if end == 0 {
+            return Ok(0);
+        }
+        let mut acc = M::identity();
+        Ok(self
+            .min_left_rec(0, 0, self.capacity - 1, end, &mut acc, &pred, &None)
+            .map_or(0, |failed| failed + 1))
+    }
+
+    //Returns the last index where the suffix ending at end stops satisfying pred, acc is the combination of the suffix after v.
+    #[allow(clippy::too_many_arguments)]
+    fn min_left_rec<P>(
+        &self,
+        v: usize,
+        low: usize,
+        high: usize,
+        end: usize,
+        acc: &mut M::Value,
+        pred: &P,
+        pending: &Option<A>,
+    ) -> Option<usize>
+    where
+        P: Fn(&M::Value) -> bool,
+    {
+        if end <= low {
+            return None;
+        }
+        if high < end {
+            let combined = M::combine(&self.value(v, low, high, pending), acc);
+            if pred(&combined) {
+                *acc = combined;
+                return None;
+            }
+            if low == high {
+                return Some(low);
+            }
+        }
+
+        let mid = (low + high) / 2;
+        let below = self.pending_below(mid, pending);
+        self.min_left_rec(
+            right_child(v, low, mid),
+            mid + 1,
+            high,
+            end,
+            acc,
+            pred,
+            &below,
+        )
+        .or_else(|| self.min_left_rec(v + 1, low, mid, end, acc, pred, &below))
+    }
+
+    //Value of the node v covering [low, high] with the pending action of its ancestors applied.
+    fn value(&self, v: usize, low: usize, high: usize, pending: &Option<A>) -> M::Value {
+        match pending {
+            Some(action) => action.apply(&self.tree[v], high - low + 1),
+            None => self.tree[v].clone(),
+        }
+    }
+
+    //The queries do not push: they carry down the tags they meet instead. The tags of the ancestors are newer than
+    //the one of the node, so the children of the node splitting after mid get its tag first and then `pending`.
+    //This is the tag a push would leave on them, so it never breaks.
+    fn pending_below(&self, mid: usize, pending: &Option<A>) -> Option<A> {
+        match (pending, &self.lazy[mid]) {
+            (Some(newer), Some(tag)) => Some(newer.compose(tag)),
+            (newer, tag) => newer.clone().or_else(|| tag.clone()),
+        }
+    }
+
+    //Applies the action to the node value and stores it for the children.
+    //If the action breaks on the node it goes down to the children instead, a pushed tag never breaks
+    //since it has already been applied to the whole parent.
+    fn apply_node(&mut self, v: usize, low: usize, high: usize, action: &A) {
+        if low != high && action.breaks_on(&self.tree[v]) {
+            self.push_down(v, low, high);
+            let mid = (low + high) / 2;
+            self.apply_node(v + 1, low, mid, action);
+            self.apply_node(right_child(v, low, mid), mid + 1, high, action);
+            self.pull(v, low, mid);
+            return;
+        }
+        self.set_node(v, action.apply(&self.tree[v], high - low + 1));
+        if low != high {
+            let mid = (low + high) / 2;
+            let tag = match self.take_tag(mid) {
+                Some(pending) => action.compose(&pending),
+                None => action.clone(),
+            };
+            self.lazy[mid] = Some(tag);
+        }
+    }
+
+    //Moves the pending tag of v to its children.
+    fn push_down(&mut self, v: usize, low: usize, high: usize) {
+        let mid = (low + high) / 2;
+        if let Some(action) = self.take_tag(mid) {
+            self.apply_node(v + 1, low, mid, &action);
+            self.apply_node(right_child(v, low, mid), mid + 1, high, &action);
+        }
+    }
+
+    fn pull(&mut self, v: usize, low: usize, mid: usize) {
+        self.set_node(
+            v,
+            M::combine(&self.tree[v + 1], &self.tree[right_child(v, low, mid)]),
+        );
+    }
+
+    //All the writes after the build go through set_node and take_tag, which journal the old contents while recording.
+    //take_tag leaves the slot empty, so a tag written right after it is undone by the same change.
+    fn set_node(&mut self, v: usize, value: M::Value) {
+        let old = std::mem::replace(&mut self.tree[v], value);
+        if self.recording {
+            self.journal.push(Change::Node(v, old));
+        }
+    }
+
+    fn take_tag(&mut self, mid: usize) -> Option<A> {
+        let old = self.lazy[mid].take();
+        if self.recording {
+            self.journal.push(Change::Tag(mid, old.clone()));
+        }
+        old
+    }
+}
+
+//Iterator over the elements of a LazySegmentTree with the pending updates applied, see LazySegmentTree::leaves.
+pub struct Leaves<'a, M: Monoid, A: Action<M>> {
+    tree: &'a LazySegmentTree<M, A>,
+    //Subtrees still to visit, the next one on top: node, low, high and the pending action of its ancestors.
+    stack: Vec<(usize, usize, usize, Option<A>)>,
+    remaining: usize,
+}
+
+impl<M: Monoid, A: Action<M>> Iterator for Leaves<'_, M, A> {
+    type Item = M::Value;
+
+    //Goes down the left children to the next leaf, leaving the right ones on the stack.
+    //Every node is entered once over the whole iteration, the iteration stops before the leaves past the elements.
+    fn next(&mut self) -> Option<M::Value> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let (mut v, low, mut high, mut pending) = self.stack.pop()?;
+        while low != high {
+            let mid = (low + high) / 2;
+            let below = self.tree.pending_below(mid, &pending);
+            self.stack
+                .push((right_child(v, low, mid), mid + 1, high, below.clone()));
+            (v, high, pending) = (v + 1, mid, below);
+        }
+        self.remaining -= 1;
+        Some(self.tree.value(v, low, high, &pending))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<M: Monoid, A: Action<M>> ExactSizeIterator for Leaves<'_, M, A> {}
+
+impl<M, A> fmt::Debug for LazySegmentTree<M, A>
+where
+    M: Monoid,
+    M::Value: fmt::Debug,
+    A: Action<M> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazySegmentTree")
+            .field("size", &self.size)
+            .field("capacity", &self.capacity)
+            .field("tree", &self.tree)
+            .field("lazy", &self.lazy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::{Add, Assign, ChMax, ChMin, Max, MaxCount, MaxInfo, Min, MinMax, Sum};
+    use crate::rng::Rng;
+
+    //Runs random updates and queries against a plain array.
+    fn check_against_naive<M, A>(
+        seed: u64,
+        value: impl Fn(&mut Rng) -> M::Value,
+        action: impl Fn(&mut Rng) -> A,
+        apply: impl Fn(&A, &M::Value) -> M::Value,
+    ) where
+        M: Monoid,
+        M::Value: PartialEq + fmt::Debug,
+        A: Action<M>,
+    {
+        let mut rng = Rng::new(seed);
+        for _ in 0..20 {
+            let n = 1 + rng.below(70);
+            let mut naive: Vec<M::Value> = (0..n).map(|_| value(&mut rng)).collect();
+            let mut tree = LazySegmentTree::<M, A>::new(&naive);
+
+            for _ in 0..200 {
+                let mut left = rng.below(n);
+                let mut right = rng.below(n);
+                if left > right {
+                    std::mem::swap(&mut left, &mut right);
+                }
+
+                if rng.below(2) == 0 {
+                    let a = action(&mut rng);
+                    naive[left..=right]
+                        .iter_mut()
+                        .for_each(|x| *x = apply(&a, x));
+                    tree.update(left..=right, a);
+                } else {
+                    let expected = naive[left..=right]
+                        .iter()
+                        .fold(M::identity(), |acc, x| M::combine(&acc, x));
+                    assert_eq!(tree.query(left..=right), expected);
+                }
+            }
+        }
+    }
+
+    fn small(rng: &mut Rng) -> i64 {
+        rng.below(2001) as i64 - 1000
+    }
+
+    #[test]
+    fn test_max_chmin() {
+        check_against_naive::<Max<i64>, ChMin<i64>>(
+            1,
+            small,
+            |rng| ChMin(small(rng)),
+            |a, x| (*x).min(a.0),
+        );
+    }
+
+    #[test]
+    fn test_min_chmax() {
+        check_against_naive::<Min<i64>, ChMax<i64>>(
+            2,
+            small,
+            |rng| ChMax(small(rng)),
+            |a, x| (*x).max(a.0),
+        );
+    }
+
+    #[test]
+    fn test_sum_add() {
+        check_against_naive::<Sum<i64>, Add<i64>>(3, small, |rng| Add(small(rng)), |a, x| x + a.0);
+    }
+
+    #[test]
+    fn test_sum_assign() {
+        check_against_naive::<Sum<i64>, Assign<i64>>(
+            4,
+            small,
+            |rng| Assign(small(rng)),
+            |a, _| a.0,
+        );
+    }
+
+    #[test]
+    fn test_min_assign() {
+        check_against_naive::<Min<i64>, Assign<i64>>(
+            5,
+            small,
+            |rng| Assign(small(rng)),
+            |a, _| a.0,
+        );
+    }
+
+    #[test]
+    fn test_max_add() {
+        check_against_naive::<Max<i64>, Add<i64>>(6, small, |rng| Add(small(rng)), |a, x| x + a.0);
+    }
+
+    #[test]
+    fn test_min_max_add() {
+        check_against_naive::<MinMax<i64>, Add<i64>>(
+            7,
+            |rng| {
+                let x = small(rng);
+                (x, x)
+            },
+            |rng| Add(small(rng)),
+            |a, x| (x.0 + a.0, x.1 + a.0),
+        );
+    }
+
+    //The count and the second max of MaxCount under chmin need the beats descent.
+    #[test]
+    fn test_max_count_chmin() {
+        let info = |values: &[i64]| {
+            values.iter().fold(MaxCount::identity(), |acc, &x| {
+                MaxCount::combine(&acc, &MaxInfo::leaf(x))
+            })
+        };
+        let mut rng = Rng::new(10);
+        for _ in 0..20 {
+            let n = 1 + rng.below(70);
+            let mut naive: Vec<i64> = (0..n).map(|_| rng.below(30) as i64).collect();
+            let leaves: Vec<MaxInfo<i64>> = naive.iter().map(|&x| MaxInfo::leaf(x)).collect();
+            let mut tree = LazySegmentTree::<MaxCount<i64>, ChMin<i64>>::new(&leaves);
+
+            for _ in 0..300 {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let range = a.min(b)..=a.max(b);
+                if rng.below(2) == 0 {
+                    let t = rng.below(30) as i64;
+                    naive[range.clone()]
+                        .iter_mut()
+                        .for_each(|x| *x = (*x).min(t));
+                    tree.update(range, ChMin(t));
+                } else {
+                    assert_eq!(tree.query(range.clone()), info(&naive[range]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pair_of_monoids() {
+        check_against_naive::<(Min<i64>, Sum<i64>), Add<i64>>(
+            11,
+            |rng| {
+                let x = small(rng);
+                (x, x)
+            },
+            |rng| Add(small(rng)),
+            |a, x| (x.0 + a.0, x.1 + a.0),
+        );
+    }
+
+    #[test]
+    fn test_find_first() {
+        let mut tree = LazySegmentTree::<Max<i32>, ChMin<i32>>::new(&[5, 1, 4, 3, 2, 7, 4]);
+        let at_least = |x: i32| move |v: &i32| *v >= x;
+        assert_eq!(tree.find_first(0..=6, at_least(4), at_least(4)), Some(0));
+        assert_eq!(tree.find_first(1..=6, at_least(4), at_least(4)), Some(2));
+        assert_eq!(tree.find_first(3..=6, at_least(6), at_least(6)), Some(5));
+        assert_eq!(tree.find_first(0..=4, at_least(6), at_least(6)), None);
+
+        //Exact search: prune with the max, match only the value.
+        assert_eq!(tree.find_first(0..=6, |v| *v >= 3, |v| *v == 3), Some(3));
+        tree.update(.., ChMin(3));
+        assert_eq!(tree.find_first(0..=6, |v| *v >= 3, |v| *v == 3), Some(0));
+        assert_eq!(tree.find_first(1..=1, |v| *v >= 3, |v| *v == 3), None);
+    }
+
+    #[test]
+    fn test_exact_search_against_naive() {
+        let mut rng = Rng::new(8);
+        for _ in 0..30 {
+            let n = 1 + rng.below(60);
+            let values: Vec<(i64, i64)> = (0..n)
+                .map(|_| {
+                    let x = rng.below(6) as i64;
+                    (x, x)
+                })
+                .collect();
+            let tree = LazySegmentTree::<MinMax<i64>, Add<i64>>::new(&values);
+
+            for _ in 0..100 {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let (left, right) = (a.min(b), a.max(b));
+                let k = rng.below(6) as i64;
+                let positions: Vec<usize> = (left..=right).filter(|&i| values[i].0 == k).collect();
+
+                //A subtree can contain k only if min <= k <= max.
+                let may_contain = |&(min, max): &(i64, i64)| min <= k && k <= max;
+                let is_match = |&(value, _): &(i64, i64)| value == k;
+                assert_eq!(
+                    tree.find_first(left..=right, may_contain, is_match),
+                    positions.first().copied()
+                );
+                assert_eq!(
+                    tree.find_last(left..=right, may_contain, is_match),
+                    positions.last().copied()
+                );
+                assert_eq!(
+                    tree.count_matches(left..=right, may_contain, is_match),
+                    positions.len()
+                );
+            }
+        }
+    }
+
+    //Counts the nodes visited by a search, that is the calls to may_contain.
+    fn visits(search: impl FnOnce(&dyn Fn(&(i64, i64)) -> bool), k: i64) -> usize {
+        let calls = std::cell::Cell::new(0);
+        let may_contain = |&(min, max): &(i64, i64)| {
+            calls.set(calls.get() + 1);
+            min <= k && k <= max
+        };
+        search(&may_contain);
+        calls.get()
+    }
+
+    #[test]
+    fn test_exact_search_worst_case() {
+        let n = 1 << 14;
+        let log = 15;
+
+        //Values k - 1 and k + 1 alternate: every node has min < k < max but no leaf is k,
+        //so every search visits the whole tree, the bound O((1 + c) log n) with c = n - 1 jumps over k.
+        let alternating: Vec<(i64, i64)> =
+            (0..n as i64).map(|i| (2 * (i % 2), 2 * (i % 2))).collect();
+        let tree = LazySegmentTree::<MinMax<i64>, Add<i64>>::new(&alternating);
+        let is_match = |&(value, _): &(i64, i64)| value == 1;
+        let first = visits(
+            |may_contain| assert_eq!(tree.find_first(.., may_contain, is_match), None),
+            1,
+        );
+        assert_eq!(first, 2 * n - 1);
+        let count = visits(
+            |may_contain| assert_eq!(tree.count_matches(.., may_contain, is_match), 0),
+            1,
+        );
+        assert_eq!(count, 2 * n - 1);
+
+        //Consecutive values differ by at most 1: a node with min <= k <= max always contains k,
+        //so a search for a single position goes down one path (plus the borders of the range).
+        let ramp: Vec<(i64, i64)> = (0..n as i64).map(|i| (i, i)).collect();
+        let tree = LazySegmentTree::<MinMax<i64>, Add<i64>>::new(&ramp);
+        for k in [0, 1, 777, n as i64 - 1] {
+            let is_match = move |&(value, _): &(i64, i64)| value == k;
+            let first = visits(
+                |may_contain| {
+                    assert_eq!(tree.find_first(.., may_contain, is_match), Some(k as usize))
+                },
+                k,
+            );
+            assert!(first <= 2 * log, "{} visits for k = {}", first, k);
+            let last = visits(
+                |may_contain| {
+                    assert_eq!(
+                        tree.find_last(3.., may_contain, is_match),
+                        (k >= 3).then_some(k as usize)
+                    )
+                },
+                k,
+            );
+            assert!(last <= 4 * log, "{} visits for k = {}", last, k);
+            let count = visits(
+                |may_contain| assert_eq!(tree.count_matches(.., may_contain, is_match), 1),
+                k,
+            );
+            assert!(count <= 2 * log, "{} visits for k = {}", count, k);
+        }
+    }
+
+    #[test]
+    fn test_max_right_min_left_against_naive() {
+        let mut rng = Rng::new(9);
+        for _ in 0..30 {
+            let n = 1 + rng.below(50);
+            let mut naive: Vec<i64> = (0..n).map(|_| small(&mut rng)).collect();
+            let mut tree = LazySegmentTree::<Max<i64>, ChMin<i64>>::new(&naive);
+
+            for _ in 0..200 {
+                let x = small(&mut rng);
+                if rng.below(3) == 0 {
+                    let a = rng.below(n);
+                    let b = rng.below(n);
+                    tree.update(a.min(b)..=a.max(b), ChMin(x));
+                    naive[a.min(b)..=a.max(b)]
+                        .iter_mut()
+                        .for_each(|v| *v = (*v).min(x));
+                    continue;
+                }
+
+                //Longest prefix from start whose max stays below x, and longest suffix ending at end.
+                let start = rng.below(n + 1);
+                let expected = (start..n).find(|&i| naive[i] >= x).unwrap_or(n);
+                assert_eq!(tree.max_right(start, |&max| max < x), expected);
+
+                let end = rng.below(n + 1);
+                let expected = (0..end).rev().find(|&i| naive[i] >= x).map_or(0, |i| i + 1);
+                assert_eq!(tree.min_left(end, |&max| max < x), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_right_with_sum() {
+        let mut tree = LazySegmentTree::<Sum<i64>, Add<i64>>::new(&[1, 2, 3, 4, 5]);
+        assert_eq!(tree.max_right(0, |&sum| sum <= 6), 3);
+        assert_eq!(tree.max_right(1, |&sum| sum <= 6), 3);
+        assert_eq!(tree.min_left(5, |&sum| sum <= 9), 3);
+        tree.update(.., Add(-1));
+        assert_eq!(tree.max_right(0, |&sum| sum <= 6), 4);
+        assert_eq!(tree.max_right(5, |_| false), 5);
+        assert_eq!(tree.min_left(0, |_| false), 0);
+        assert!(tree.try_max_right(6, |_| true).is_err());
+        assert!(tree.try_min_left(6, |_| true).is_err());
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut tree = LazySegmentTree::<Sum<i32>, Add<i32>>::new(&[1, 2, 3]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert_eq!(
+            tree.try_query(reversed),
+            Err(RangeError::Reversed { start: 2, end: 1 })
+        );
+        assert_eq!(
+            tree.try_update(1..=3, Add(10)),
+            Err(RangeError::OutOfBounds { end: 4, len: 3 })
+        );
+        assert!(tree.try_find_first(4.., |_| true, |_| true).is_err());
+        assert_eq!(tree.query(..), 6);
+        assert_eq!(tree.try_query(1..), Ok(5));
+        assert_eq!(tree.len(), 3);
+        assert!(!tree.is_empty());
+    }
+
+    #[test]
+    fn test_empty_ranges() {
+        let mut tree = LazySegmentTree::<Max<i32>, Assign<i32>>::new(&[i32::MIN, 2, i32::MAX]);
+        //The empty range gives the identity, a real i32::MIN is still found by the searches.
+        assert_eq!(tree.query(2..2), i32::MIN);
+        assert_eq!(tree.find_first(..1, |_| true, |&v| v == i32::MIN), Some(0));
+        assert_eq!(tree.find_first(3.., |_| true, |_| true), None);
+        assert_eq!(tree.find_last(1..1, |_| true, |_| true), None);
+        assert_eq!(tree.count_matches(0..0, |_| true, |_| true), 0);
+
+        //An update on an empty range does nothing, an i32::MAX tag is a real tag.
+        tree.update(1..1, Assign(0));
+        assert_eq!(tree.query(..), i32::MAX);
+        tree.update(.., Assign(i32::MAX));
+        assert_eq!(tree.query(..1), i32::MAX);
+
+        let mut empty = LazySegmentTree::<Sum<i32>, Add<i32>>::new(&[]);
+        assert_eq!(empty.query(..), 0);
+        empty.update(.., Add(1));
+        assert!(empty.is_empty());
+    }
+
+    //2n - 1 values and n - 1 tags for every size, not only the powers of two.
+    #[test]
+    fn test_compact_layout() {
+        for n in 1..200usize {
+            let values: Vec<i64> = (0..n as i64).collect();
+            let mut tree = LazySegmentTree::<Sum<i64>, Add<i64>>::new(&values);
+            assert_eq!(
+                tree.memory_bytes(),
+                (2 * n - 1) * std::mem::size_of::<i64>()
+                    + (n - 1) * std::mem::size_of::<Option<Add<i64>>>()
+            );
+
+            //Every prefix update stores tags on internal nodes that the point queries must push down.
+            for end in 1..=n {
+                tree.update(..end, Add(1));
+            }
+            //Position i gets n - i of the additions, so every position now holds n.
+            for i in 0..n {
+                assert_eq!(tree.query(i..=i), n as i64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_vec_against_naive() {
+        let mut rng = Rng::new(46);
+        for n in 0..70 {
+            let mut naive: Vec<i64> = (0..n).map(|_| small(&mut rng)).collect();
+            let leaves: Vec<MaxInfo<i64>> = naive.iter().map(|&x| MaxInfo::leaf(x)).collect();
+            let mut tree = LazySegmentTree::<MaxCount<i64>, ChMin<i64>>::new(&leaves);
+            for _ in 0..n {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let t = small(&mut rng);
+                tree.update(a.min(b)..=a.max(b), ChMin(t));
+                naive[a.min(b)..=a.max(b)]
+                    .iter_mut()
+                    .for_each(|x| *x = (*x).min(t));
+            }
+
+            let expected: Vec<MaxInfo<i64>> = naive.iter().map(|&x| MaxInfo::leaf(x)).collect();
+            assert_eq!(tree.leaves().len(), n);
+            assert_eq!(tree.to_vec(), expected);
+            //Reading the array does not push, the tags are still there for the next queries.
+            assert_eq!(tree.to_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn test_rebuild_from_reuses_buffers() {
+        let mut tree = LazySegmentTree::<Sum<i64>, Add<i64>>::new(&[1, 2, 3, 4, 5]);
+        tree.update(.., Add(10));
+        let (values, tags) = (tree.tree.as_ptr(), tree.lazy.as_ptr());
+
+        tree.rebuild_from(&[7, 8, 9]);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.to_vec(), [7, 8, 9]);
+        assert_eq!(tree.query(..), 24);
+        //The old tags are gone, they must not be applied to the new array.
+        assert_eq!(tree.query(1..2), 8);
+        assert_eq!((tree.tree.as_ptr(), tree.lazy.as_ptr()), (values, tags));
+
+        tree.rebuild_from(&[]);
+        assert!(tree.is_empty());
+        assert_eq!(tree.to_vec(), []);
+        tree.rebuild_from(&[1; 9]);
+        assert_eq!(tree.query(..), 9);
+    }
+
+    //Depth first search over random updates: every branch is undone before the next one starts.
+    #[test]
+    fn test_rollback_against_naive() {
+        let mut rng = Rng::new(47);
+        for _ in 0..20 {
+            let n = 1 + rng.below(60);
+            let mut naive: Vec<i64> = (0..n).map(|_| small(&mut rng)).collect();
+            let mut tree = LazySegmentTree::<Sum<i64>, Add<i64>>::new(&naive);
+            let mut saved = Vec::new();
+
+            for _ in 0..300 {
+                match rng.below(4) {
+                    0 => saved.push((tree.checkpoint(), naive.clone())),
+                    1 => {
+                        if let Some((checkpoint, before)) = saved.pop() {
+                            tree.rollback(checkpoint);
+                            naive = before;
+                            assert_eq!(tree.to_vec(), naive);
+                        }
+                    }
+                    _ => {
+                        let a = rng.below(n);
+                        let b = rng.below(n);
+                        let add = small(&mut rng);
+                        tree.update(a.min(b)..=a.max(b), Add(add));
+                        naive[a.min(b)..=a.max(b)]
+                            .iter_mut()
+                            .for_each(|x| *x += add);
+                        let c = rng.below(n);
+                        assert_eq!(tree.query(c..), naive[c..].iter().sum::<i64>());
+                    }
+                }
+            }
+            while let Some((checkpoint, before)) = saved.pop() {
+                tree.rollback(checkpoint);
+                assert_eq!(tree.to_vec(), before);
+            }
+        }
+    }
+
+    //The beats descent pushes and pulls below the range of the update, it must be undone as well.
+    #[test]
+    fn test_rollback_beats() {
+        let values: Vec<MaxInfo<i64>> = [5, 3, 5, 1, 4, 5, 2].map(MaxInfo::leaf).to_vec();
+        let mut tree = LazySegmentTree::<MaxCount<i64>, ChMin<i64>>::new(&values);
+        let checkpoint = tree.checkpoint();
+        tree.update(.., ChMin(4));
+        tree.update(2..5, ChMin(2));
+        assert_eq!(tree.to_vec(), [4, 3, 2, 1, 2, 4, 2].map(MaxInfo::leaf));
+        assert_eq!(tree.query(..).count, 2);
+        tree.rollback(checkpoint);
+        assert_eq!(tree.to_vec(), values);
+        assert_eq!(tree.query(..).count, 3);
+        assert_eq!(tree.query(..).second_max, Some(4));
+        //The checkpoint is still valid after going back to it.
+        tree.update(.., ChMin(0));
+        tree.rollback(checkpoint);
+        assert_eq!(tree.to_vec(), values);
+    }
+
+    #[test]
+    #[should_panic(expected = "Checkpoint no longer valid")]
+    fn test_rollback_after_rebuild() {
+        let mut tree = LazySegmentTree::<Sum<i64>, Add<i64>>::new(&[1, 2, 3]);
+        let checkpoint = tree.checkpoint();
+        tree.update(.., Add(1));
+        tree.rebuild_from(&[4, 5]);
+        tree.rollback(checkpoint);
+    }
+
+    //Pushes, pops and updates in any order, with the tags of the updates pending when the storage grows.
+    #[test]
+    fn test_push_pop_against_naive() {
+        let mut rng = Rng::new(48);
+        for _ in 0..20 {
+            let mut naive: Vec<i64> = (0..rng.below(5)).map(|_| small(&mut rng)).collect();
+            let mut tree = LazySegmentTree::<MinMax<i64>, Add<i64>>::new(
+                &naive.iter().map(|&x| (x, x)).collect::<Vec<_>>(),
+            );
+
+            for _ in 0..300 {
+                let n = naive.len();
+                match rng.below(6) {
+                    0 | 1 => {
+                        let x = small(&mut rng);
+                        tree.push((x, x));
+                        naive.push(x);
+                    }
+                    2 => assert_eq!(tree.pop(), naive.pop().map(|x| (x, x))),
+                    3 if n > 0 => {
+                        let i = rng.below(n);
+                        let x = small(&mut rng);
+                        tree.set(i, (x, x));
+                        naive[i] = x;
+                    }
+                    _ => {
+                        let a = rng.below(n + 1);
+                        let b = rng.below(n + 1);
+                        let add = small(&mut rng);
+                        tree.update(a.min(b)..a.max(b), Add(add));
+                        naive[a.min(b)..a.max(b)].iter_mut().for_each(|x| *x += add);
+                    }
+                }
+                assert_eq!(tree.len(), naive.len());
+                assert!(tree.capacity() >= tree.len());
+                let min = naive.iter().min().copied().unwrap_or(i64::MAX);
+                let max = naive.iter().max().copied().unwrap_or(i64::MIN);
+                assert_eq!(tree.query(..), (min, max));
+            }
+            assert_eq!(
+                tree.to_vec(),
+                naive.iter().map(|&x| (x, x)).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_doubles_capacity() {
+        let mut tree = LazySegmentTree::<Sum<i64>, Add<i64>>::new(&[]);
+        let mut capacities = Vec::new();
+        for i in 0..100 {
+            tree.push(i);
+            tree.update(.., Add(1));
+            if capacities.last() != Some(&tree.capacity()) {
+                capacities.push(tree.capacity());
+            }
+        }
+        assert_eq!(capacities, [1, 2, 4, 8, 16, 32, 64, 128]);
+        //The element i got the 100 - i additions made after its push.
+        assert_eq!(tree.to_vec(), vec![100; 100]);
+        //The leaves past the elements hold the identity, max_right does not run into them.
+        assert_eq!(tree.max_right(0, |&sum| sum < 1_000_000), 100);
+
+        tree.resize(10, 0);
+        assert_eq!(tree.capacity(), 128);
+        assert_eq!(tree.query(..), 1000);
+        tree.resize(300, 1);
+        assert_eq!(tree.capacity(), 300);
+        assert_eq!(tree.query(..), 1290);
+    }
+
+    //A rollback past a growth puts the old storage back.
+    #[test]
+    fn test_rollback_push_pop() {
+        let mut tree = LazySegmentTree::<Sum<i64>, Add<i64>>::new(&[1, 2, 3]);
+        let checkpoint = tree.checkpoint();
+        tree.update(.., Add(10));
+        for i in 0..10 {
+            tree.push(i);
+        }
+        tree.pop();
+        let inner = tree.checkpoint();
+        tree.resize(2, 0);
+        assert_eq!(tree.to_vec(), [11, 12]);
+        tree.rollback(inner);
+        assert_eq!(tree.len(), 12);
+        assert_eq!(tree.query(..), 36 + 36);
+        tree.rollback(checkpoint);
+        assert_eq!(tree.to_vec(), [1, 2, 3]);
+        assert_eq!(tree.capacity(), 3);
+        assert_eq!(
+            tree.memory_bytes(),
+            5 * 8 + 2 * std::mem::size_of::<Option<Add<i64>>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_query_out_of_bounds() {
+        let tree = LazySegmentTree::<Sum<i32>, Add<i32>>::new(&[1, 2, 3]);
+        tree.query(0..4);
+    }
+}
@@ -1,128 +1,411 @@
+pub mod beats;
+pub mod lazy;
+pub mod merge_sort;
+pub mod ops;
+pub mod range;
+pub mod rng;
+pub mod treap;
+pub mod wavelet;
+
+use lazy::LazySegmentTree;
+use ops::{ChMin, MaxCount, MaxInfo};
+use range::RangeError;
+use std::ops::RangeBounds;
+
+//Range max queries with range chmin updates (a[i] = min(a[i], t)).
+//The nodes keep how many positions hold the max and the second max too (MaxCount), so a chmin that makes
+//some maxima equal goes down until it can update the count (Segment Tree Beats).
 #[derive(Debug)]
 pub struct SegmentTree {
-    size: usize,
-    tree: Vec<i32>,
-    lazy: Vec<i32>,
+    tree: LazySegmentTree<MaxCount<i32>, ChMin<i32>>,
 }
 
 impl SegmentTree {
     pub fn new(array: &[i32]) -> Self {
-        let tree = SegmentTree::create_tree(array);
-        let lazy = vec![i32::MAX; array.len().next_power_of_two() * 2 - 1];
         SegmentTree {
-            size: array.len(),
-            tree,
-            lazy,
+            tree: LazySegmentTree::new(
+                &array.iter().map(|&x| MaxInfo::leaf(x)).collect::<Vec<_>>(),
+            ),
         }
     }
 
-    fn create_tree(array: &[i32]) -> Vec<i32> {
-        let next_power = array.len().next_power_of_two();
-        let mut tree = vec![0; next_power * 2 - 1];
+    //Replaces the array with a new one, of any length, reusing the memory of the tree. O(n).
+    pub fn rebuild_from(&mut self, array: &[i32]) {
+        self.tree
+            .rebuild_with(array.len(), |i| MaxInfo::leaf(array[i]));
+    }
 
-        for i in 0..array.len() {
-            tree[i] = i32::MIN;
-        }
-        SegmentTree::construct_tree(&mut tree, array, 0, array.len() - 1, 0);
-        return tree;
+    //The current array, with every pending chmin applied. O(n).
+    pub fn to_vec(&self) -> Vec<i32> {
+        self.leaves().collect()
     }
 
-    fn construct_tree(tree: &mut [i32], input: &[i32], low: usize, high: usize, v: usize) {
-        if low == high {
-            tree[v] = input[low];
-            return;
-        }
-        let mid = (high + low) / 2;
-        Self::construct_tree(tree, input, low, mid, 2 * v + 1);
-        Self::construct_tree(tree, input, mid + 1, high, 2 * v + 2);
-        tree[v] = tree[v * 2 + 1].max(tree[v * 2 + 2]);
+    //The current values in order, without building the whole array.
+    pub fn leaves(&self) -> impl ExactSizeIterator<Item = i32> + '_ {
+        self.tree.leaves().map(|info| info.max)
     }
 
     pub fn reset_lazy(&mut self) {
-        self.lazy = vec![i32::MAX; self.size.next_power_of_two() * 2 - 1];
+        self.tree.reset_lazy();
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    //Appends a value, amortised O(log n): the storage doubles when it is full and keeps the pending chmins.
+    pub fn push(&mut self, value: i32) {
+        self.tree.push(MaxInfo::leaf(value));
+    }
+
+    //Removes the last value and returns it, None if the tree is empty. O(log n).
+    pub fn pop(&mut self) -> Option<i32> {
+        self.tree.pop().map(|info| info.max)
+    }
+
+    //Removes the last values or appends copies of fill until there are len.
+    pub fn resize(&mut self, len: usize, fill: i32) {
+        self.tree.resize(len, MaxInfo::leaf(fill));
+    }
+
+    //Max in the range, positions are 0 indexed. None if the range is empty, panics if it is reversed or out of bounds.
+    pub fn max_query(&self, range: impl RangeBounds<usize>) -> Option<i32> {
+        self.max_info(range).map(|info| info.max)
+    }
+
+    pub fn try_max_query(&self, range: impl RangeBounds<usize>) -> Result<Option<i32>, RangeError> {
+        Ok(self.try_max_info(range)?.map(|info| info.max))
+    }
+
+    //Max of the range with how many positions hold it and the second max, None if the range is empty.
+    pub fn max_info(&self, range: impl RangeBounds<usize>) -> Option<MaxInfo<i32>> {
+        self.try_max_info(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_max_info(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<MaxInfo<i32>>, RangeError> {
+        //The identity, the only value with no position, is what an empty range gives.
+        let info = self.tree.try_query(range)?;
+        Ok((info.count > 0).then_some(info))
+    }
+
+    //Leftmost position of the range holding the max, None if the range is empty.
+    pub fn argmax(&self, range: impl RangeBounds<usize>) -> Option<usize> {
+        self.try_argmax(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_argmax(&self, range: impl RangeBounds<usize>) -> Result<Option<usize>, RangeError> {
+        let range = range::to_half_open(range, self.tree.len())?;
+        let Some(MaxInfo { max, .. }) = self.max_info(range.clone()) else {
+            return Ok(None);
+        };
+        //Every node inside the range with max >= the range max holds it, so the search goes down one path.
+        Ok(self.tree.find_first(
+            range,
+            |info| info.count > 0 && info.max >= max,
+            |leaf| leaf.max == max,
+        ))
+    }
+
+    //Max of every half-open range start..end of the batch, in order. The queries run on scoped threads sharing the tree.
+    //Panics if one of the ranges is reversed or out of bounds.
+    pub fn query_batch(&self, ranges: &[(usize, usize)]) -> Vec<Option<i32>> {
+        self.try_query_batch(ranges)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_query_batch(
+        &self,
+        ranges: &[(usize, usize)],
+    ) -> Result<Vec<Option<i32>>, RangeError> {
+        Ok(self
+            .tree
+            .try_query_batch(ranges)?
+            .into_iter()
+            .map(|info| (info.count > 0).then_some(info.max))
+            .collect())
+    }
+
+    //Number of positions of the range holding the max, 0 if the range is empty.
+    pub fn max_count(&self, range: impl RangeBounds<usize>) -> usize {
+        self.tree.query(range).count
+    }
+
+    pub fn try_max_count(&self, range: impl RangeBounds<usize>) -> Result<usize, RangeError> {
+        Ok(self.tree.try_query(range)?.count)
+    }
+
+    //Largest value of the range strictly below the max, None if all the positions hold the max.
+    pub fn second_max(&self, range: impl RangeBounds<usize>) -> Option<i32> {
+        self.tree.query(range).second_max
     }
 
-    pub fn max_query(&mut self, qlow: usize, qhigh: usize) -> i32 {
-        self.max_query_rec(qlow - 1, qhigh - 1, 0, self.size - 1, 0)
+    pub fn try_second_max(
+        &self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<i32>, RangeError> {
+        Ok(self.tree.try_query(range)?.second_max)
     }
 
-    //TODO this is 0 indexed and should be tested.
-    fn max_query_rec(
+    //Sets a[i] = min(a[i], new_val) for every i in the range.
+    pub fn update(&mut self, range: impl RangeBounds<usize>, new_val: i32) {
+        self.tree.update(range, ChMin(new_val));
+    }
+
+    pub fn try_update(
         &mut self,
-        qlow: usize,  //qlow
-        qhigh: usize, //qhigh
-        low: usize,   //low
-        high: usize,  //high
-        v: usize,
-    ) -> i32 {
-        if low > high {
-            return i32::MIN; // Return the minimum value for an empty range
-        }
-        self.push(v, low, high);
+        range: impl RangeBounds<usize>,
+        new_val: i32,
+    ) -> Result<(), RangeError> {
+        self.tree.try_update(range, ChMin(new_val))
+    }
 
-        if qlow > high || qhigh < low {
-            return i32::MIN;
-        }
-        if qlow <= low && qhigh >= high {
-            return self.tree[v];
-        }
-        let mid = (low + high) / 2;
-        return self
-            .max_query_rec(qlow, qhigh, low, mid, 2 * v + 1)
-            .max(self.max_query_rec(qlow, qhigh, mid + 1, high, 2 * v + 2));
-    }
-
-    fn push(&mut self, v: usize, low: usize, high: usize) {
-        if self.lazy[v] != 0 {
-            self.tree[v] = self.tree[v].min(self.lazy[v]);
-            if low != high {
-                self.lazy[2 * v + 1] = self.lazy[v].min(self.lazy[2 * v + 1]);
-                self.lazy[2 * v + 2] = self.lazy[v].min(self.lazy[2 * v + 2]);
+    //Largest end such that pred holds for the max of start..end (an empty range always satisfies it),
+    //once false pred must stay false on larger maxima. Panics if start is out of bounds.
+    pub fn max_right(&self, start: usize, pred: impl Fn(i32) -> bool) -> usize {
+        self.tree
+            .max_right(start, |info| info.count == 0 || pred(info.max))
+    }
+
+    pub fn try_max_right(
+        &self,
+        start: usize,
+        pred: impl Fn(i32) -> bool,
+    ) -> Result<usize, RangeError> {
+        self.tree
+            .try_max_right(start, |info| info.count == 0 || pred(info.max))
+    }
+
+    //Smallest start such that pred holds for the max of start..end, same requirements of max_right.
+    pub fn min_left(&self, end: usize, pred: impl Fn(i32) -> bool) -> usize {
+        self.tree
+            .min_left(end, |info| info.count == 0 || pred(info.max))
+    }
+
+    pub fn try_min_left(
+        &self,
+        end: usize,
+        pred: impl Fn(i32) -> bool,
+    ) -> Result<usize, RangeError> {
+        self.tree
+            .try_min_left(end, |info| info.count == 0 || pred(info.max))
+    }
+
+    //First index in start..n whose value is at least x.
+    pub fn first_at_least(&self, start: usize, x: i32) -> Option<usize> {
+        let end = self.max_right(start, |max| max < x);
+        (end < self.tree.len()).then_some(end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_search() {
+        let mut tree = SegmentTree::new(&[1, 5, 2, 8, 3, 8]);
+        assert_eq!(tree.max_right(0, |max| max < 5), 1);
+        assert_eq!(tree.max_right(2, |max| max < 8), 3);
+        assert_eq!(tree.min_left(6, |max| max < 8), 6);
+        assert_eq!(tree.min_left(3, |max| max < 5), 2);
+        assert_eq!(tree.first_at_least(0, 6), Some(3));
+        assert_eq!(tree.first_at_least(4, 9), None);
+
+        //The chmin is still a pending tag when the search goes down.
+        tree.update(2..5, 4);
+        assert_eq!(tree.first_at_least(0, 6), Some(5));
+        assert_eq!(tree.first_at_least(1, 5), Some(1));
+        assert_eq!(tree.first_at_least(2, 5), Some(5));
+        assert_eq!(tree.min_left(5, |max| max < 5), 2);
+        assert!(tree.try_max_right(7, |_| true).is_err());
+        assert!(tree.try_min_left(7, |_| true).is_err());
+    }
+
+    #[test]
+    fn test_argmax_against_naive() {
+        let mut rng = rng::Rng::new(40);
+        for _ in 0..30 {
+            let n = 1 + rng.below(50);
+            let mut naive: Vec<i32> = (0..n).map(|_| rng.below(20) as i32).collect();
+            let mut tree = SegmentTree::new(&naive);
+
+            for _ in 0..200 {
+                let a = rng.below(n);
+                let b = rng.below(n);
+                let range = a.min(b)..=a.max(b);
+                if rng.below(2) == 0 {
+                    let t = rng.below(20) as i32;
+                    tree.update(range.clone(), t);
+                    naive[range].iter_mut().for_each(|x| *x = (*x).min(t));
+                    continue;
+                }
+
+                let values = &naive[range.clone()];
+                let max = *values.iter().max().unwrap();
+                let argmax = range.start() + values.iter().position(|&x| x == max).unwrap();
+                let count = values.iter().filter(|&&x| x == max).count();
+                let second = values.iter().copied().filter(|&x| x < max).max();
+                assert_eq!(tree.argmax(range.clone()), Some(argmax));
+                assert_eq!(tree.max_count(range.clone()), count);
+                assert_eq!(tree.second_max(range.clone()), second);
+                assert_eq!(
+                    tree.max_info(range),
+                    Some(MaxInfo {
+                        max,
+                        count,
+                        second_max: second
+                    })
+                );
             }
-            self.lazy[v] = i32::MAX;
         }
     }
 
-    pub fn update(&mut self, start_range: usize, end_range: usize, new_val: i32) {
-        self.update_rec(start_range, end_range, new_val, 0, self.size - 1, 0);
+    #[test]
+    fn test_chmin_merges_maxima() {
+        let mut tree = SegmentTree::new(&[7, 3, 9, 5, 9]);
+        assert_eq!(tree.max_count(..), 2);
+        assert_eq!(tree.second_max(..), Some(7));
+        assert_eq!(tree.argmax(..), Some(2));
+
+        //9, 9 and 7 all become 6: the count grows, the second max is now 5.
+        tree.update(.., 6);
+        assert_eq!(tree.max_query(..), Some(6));
+        assert_eq!(tree.max_count(..), 3);
+        assert_eq!(tree.second_max(..), Some(5));
+        assert_eq!(tree.argmax(1..), Some(2));
+
+        tree.update(.., 3);
+        assert_eq!(tree.max_count(..), 5);
+        assert_eq!(tree.second_max(..), None);
+        assert_eq!(tree.argmax(..), Some(0));
+        assert!(tree.try_argmax(6..).is_err());
     }
 
-    fn update_rec(
-        &mut self,
+    #[test]
+    fn test_to_vec_and_rebuild() {
+        let mut tree = SegmentTree::new(&[7, 3, 9, 5, 9]);
+        tree.update(1..4, 4);
+        tree.update(3.., 6);
+        assert_eq!(tree.to_vec(), [7, 3, 4, 4, 6]);
+        assert_eq!(tree.leaves().len(), 5);
 
-        start_range: usize,
-        end_range: usize,
-        new_val: i32,
-        low: usize,
-
-        high: usize,
-        v: usize,
-    ) {
-        //low and high are
-        if low > high {
-            return;
+        tree.rebuild_from(&[2, 1]);
+        assert_eq!(tree.to_vec(), [2, 1]);
+        assert_eq!(tree.max_query(..), Some(2));
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut rng = rng::Rng::new(48);
+        let mut tree = SegmentTree::new(&[]);
+        let mut naive = Vec::new();
+        for _ in 0..2000 {
+            match rng.below(4) {
+                0 => assert_eq!(tree.pop(), naive.pop()),
+                1 => {
+                    let a = rng.below(naive.len() + 1);
+                    let b = rng.below(naive.len() + 1);
+                    let t = rng.below(1000) as i32;
+                    tree.update(a.min(b)..a.max(b), t);
+                    naive[a.min(b)..a.max(b)]
+                        .iter_mut()
+                        .for_each(|x| *x = (*x).min(t));
+                }
+                _ => {
+                    let value = rng.below(1000) as i32;
+                    tree.push(value);
+                    naive.push(value);
+                }
+            }
+            assert_eq!(tree.len(), naive.len());
+            assert_eq!(tree.max_query(..), naive.iter().max().copied());
         }
+        assert_eq!(tree.to_vec(), naive);
 
-        self.push(v, low, high);
+        tree.resize(3, 0);
+        tree.resize(5, 7);
+        assert_eq!(tree.to_vec()[..3], naive[..3]);
+        assert_eq!(tree.to_vec()[3..], [7, 7]);
+    }
 
-        if start_range > high || end_range < low {
-            return;
+    //The queries take &self, so a built tree can be shared by several threads behind an Arc.
+    #[test]
+    fn test_shared_between_threads() {
+        let mut rng = rng::Rng::new(45);
+        let mut naive: Vec<i32> = (0..1000).map(|_| rng.below(1000) as i32).collect();
+        let mut tree = SegmentTree::new(&naive);
+        for _ in 0..50 {
+            let a = rng.below(1000);
+            let b = rng.below(1000);
+            let t = rng.below(1000) as i32;
+            tree.update(a.min(b)..a.max(b), t);
+            naive[a.min(b)..a.max(b)]
+                .iter_mut()
+                .for_each(|x| *x = (*x).min(t));
         }
+        let ranges: Vec<(usize, usize)> = (0..500)
+            .map(|_| {
+                let a = rng.below(1001);
+                let b = rng.below(1001);
+                (a.min(b), a.max(b))
+            })
+            .collect();
+        let expected: Vec<Option<i32>> = ranges
+            .iter()
+            .map(|&(start, end)| naive[start..end].iter().max().copied())
+            .collect();
 
-        if start_range <= low && end_range >= high {
-            self.tree[v] = self.tree[v].min(new_val);
-            if low != high {
-                self.lazy[2 * v + 1] = self.lazy[2 * v + 1].min(new_val);
-                self.lazy[2 * v + 2] = self.lazy[2 * v + 2].min(new_val);
-            }
-            return;
-        }
+        assert_eq!(tree.query_batch(&ranges), expected);
+        let shared = std::sync::Arc::new(tree);
+        let workers: Vec<_> = (0..4)
+            .map(|i| {
+                let tree = std::sync::Arc::clone(&shared);
+                let ranges = ranges[i * 125..(i + 1) * 125].to_vec();
+                std::thread::spawn(move || {
+                    ranges
+                        .iter()
+                        .map(|&(start, end)| tree.max_query(start..end))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let answers: Vec<Option<i32>> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect();
+        assert_eq!(answers, expected);
+
+        assert!(shared.try_query_batch(&[(0, 1), (2, 1001)]).is_err());
+        assert_eq!(shared.query_batch(&[]), []);
+    }
+
+    //i32::MIN and i32::MAX are ordinary values: neither an empty result nor a missing tag.
+    #[test]
+    fn test_full_domain() {
+        let mut tree = SegmentTree::new(&[i32::MIN, i32::MIN, 4]);
+        assert_eq!(tree.max_query(..2), Some(i32::MIN));
+        assert_eq!(tree.max_count(..2), 2);
+        assert_eq!(tree.argmax(1..2), Some(1));
+        assert_eq!(tree.max_query(2..2), None);
+        assert_eq!(tree.max_info(3..), None);
+        assert_eq!(tree.argmax(0..0), None);
+        assert_eq!(tree.max_count(1..1), 0);
+        assert_eq!(tree.first_at_least(0, i32::MIN), Some(0));
 
-        let mid = (low + high) / 2;
-        self.update_rec(start_range, end_range, new_val, low, mid, 2 * v + 1);
-        self.update_rec(start_range, end_range, new_val, mid + 1, high, 2 * v + 2);
-        self.tree[v] = self.tree[v]
-            .max(self.tree[2 * v + 1])
-            .max(self.tree[2 * v + 2]);
+        tree.update(.., i32::MAX);
+        assert_eq!(tree.max_query(..), Some(4));
+        tree.update(2.., i32::MIN);
+        assert_eq!(tree.max_query(..), Some(i32::MIN));
+        assert_eq!(tree.max_count(..), 3);
+        assert_eq!(tree.second_max(..), None);
     }
 }
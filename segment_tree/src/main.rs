@@ -1,15 +1,18 @@
 use segment_tree::SegmentTree;
+#[allow(clippy::items_after_test_module)]
 #[cfg(test)]
 mod tests {
     #[test]
     fn run_tests() {
         use super::*;
+        use segment_tree::range::one_based;
         use std::fs;
         use std::path::Path;
         use std::path::PathBuf;
 
         let directory_path = "src/Testset_handson2_2324_p1/";
-        let mut tree = SegmentTree::new(&[5, 1, 4, 3, 2]);
+        //One tree for all the files, rebuilt on each input.
+        let mut tree = SegmentTree::new(&[]);
 
         for i in 0..=10 {
             let input_filename = format! {"input{}.txt", i};
@@ -44,32 +47,75 @@ mod tests {
 
             let input_values = &all_input_values[1];
             let mut output_index = 0;
-            tree = SegmentTree::new(&input_values);
-            tree.reset_lazy();
+            tree.rebuild_from(input_values);
             println!("working on input: {}", input_filename);
 
             for line in all_input_values.iter().skip(2) {
                 if line[0] == 0 {
                     println!("update {:?}", line);
-                    tree.update(line[1] as usize, line[2] as usize, line[3]);
+                    tree.update(one_based(line[1] as usize, line[2] as usize), line[3]);
                 } else if line[0] == 1 {
                     println!(
-                        "{:?} {}",
+                        "{:?} {:?}",
                         output_array[output_index],
-                        tree.max_query(line[1] as usize, line[2] as usize)
+                        tree.max_query(one_based(line[1] as usize, line[2] as usize))
                     );
                     assert!(
-                        tree.max_query(line[1] as usize, line[2] as usize)
-                            == output_array[output_index]
+                        tree.max_query(one_based(line[1] as usize, line[2] as usize))
+                            == Some(output_array[output_index])
                     );
                     output_index += 1;
                 }
             }
         }
     }
+
+    //The order statistics of the ranges of every query, on the arrays of the same files.
+    #[test]
+    fn run_order_statistics() {
+        use segment_tree::merge_sort::MergeSortTree;
+        use segment_tree::range::one_based;
+        use segment_tree::wavelet::WaveletTree;
+        use std::fs;
+
+        for i in 0..=10 {
+            let input_contents =
+                fs::read_to_string(format!("src/Testset_handson2_2324_p1/input{}.txt", i))
+                    .expect("Failed to open the test file.");
+            let all_input_values: Vec<Vec<i32>> = input_contents
+                .lines()
+                .map(|line| {
+                    line.split_whitespace()
+                        .map(|s| s.parse::<i32>().unwrap())
+                        .collect()
+                })
+                .collect();
+
+            let input_values = &all_input_values[1];
+            let wavelet = WaveletTree::new(input_values);
+            let merge_sort = MergeSortTree::new(input_values);
+
+            for line in all_input_values.iter().skip(2) {
+                let range = one_based(line[1] as usize, line[2] as usize);
+                let mut sorted = input_values[range.clone()].to_vec();
+                sorted.sort();
+                let median = sorted[(sorted.len() - 1) / 2];
+                let below_median = sorted.iter().filter(|&&x| x < median).count();
+
+                assert_eq!(wavelet.median(range.clone()), Some(median));
+                assert_eq!(merge_sort.median(range.clone()), Some(median));
+                assert_eq!(
+                    wavelet.kth_smallest(range.clone(), 0),
+                    sorted.first().copied()
+                );
+                assert_eq!(wavelet.count_less(range.clone(), median), below_median);
+                assert_eq!(merge_sort.count_less(range, median), below_median);
+            }
+        }
+    }
 }
 fn main() {
     let mut tree = SegmentTree::new(&[18, 17, 13, 19, 15, 11, 20]);
-    tree.update(1, 4, 5);
-    println!("{} ", tree.max_query(1, 2));
+    tree.update(0..4, 5);
+    println!("{:?} ", tree.max_query(0..2));
 }
@@ -0,0 +1,179 @@
+//Merge-sort tree: the simpler fallback of WaveletTree, with the same queries.
+//Every node keeps the values of its range sorted, so the values of a range below a bound are counted with a
+//binary search in each of the O(log n) nodes covering it, O(log² n). The k-th smallest value is then found by a
+//binary search over all the values, O(log³ n). It takes O(n log n) memory, against O(n log σ) for the wavelet tree.
+
+use crate::range::{to_half_open, RangeError};
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug)]
+pub struct MergeSortTree<T> {
+    size: usize,
+    //Bottom-up layout: the leaves are size..2 * size, the children of i are 2i and 2i + 1 and nodes[0] is never used.
+    //Every position has exactly one path up to the node 1, which holds all the values sorted.
+    nodes: Vec<Vec<T>>,
+}
+
+impl<T: Ord + Copy> MergeSortTree<T> {
+    pub fn new(arr: &[T]) -> Self {
+        let size = arr.len();
+        let mut nodes = vec![Vec::new(); 2 * size];
+        for (i, &value) in arr.iter().enumerate() {
+            nodes[size + i].push(value);
+        }
+        for i in (1..size).rev() {
+            nodes[i] = merge(&nodes[2 * i], &nodes[2 * i + 1]);
+        }
+        MergeSortTree { size, nodes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    //Bytes taken by the sorted values and the Vec of every node.
+    pub fn memory_bytes(&self) -> usize {
+        self.nodes.iter().map(Vec::len).sum::<usize>() * std::mem::size_of::<T>()
+            + self.nodes.len() * std::mem::size_of::<Vec<T>>()
+    }
+
+    //k-th smallest value in the range, counting from 0, None if the range has at most k values. O(log³ n).
+    //Panics if the range is reversed or out of bounds.
+    pub fn kth_smallest(&self, range: impl RangeBounds<usize>, k: usize) -> Option<T> {
+        self.try_kth_smallest(range, k)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    //The answer is the smallest of all the values with more than k values of the range at most equal to it.
+    pub fn try_kth_smallest(
+        &self,
+        range: impl RangeBounds<usize>,
+        k: usize,
+    ) -> Result<Option<T>, RangeError> {
+        let range = to_half_open(range, self.size)?;
+        if k >= range.len() {
+            return Ok(None);
+        }
+        let all = &self.nodes[1];
+        let index = all.partition_point(|&value| {
+            self.count_where(range.clone(), |sorted| {
+                sorted.partition_point(|&x| x <= value)
+            }) <= k
+        });
+        Ok(Some(all[index]))
+    }
+
+    //Number of values in the range strictly smaller than x. O(log² n).
+    //Panics if the range is reversed or out of bounds.
+    pub fn count_less(&self, range: impl RangeBounds<usize>, x: T) -> usize {
+        self.try_count_less(range, x)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_count_less(
+        &self,
+        range: impl RangeBounds<usize>,
+        x: T,
+    ) -> Result<usize, RangeError> {
+        let range = to_half_open(range, self.size)?;
+        Ok(self.count_where(range, |sorted| sorted.partition_point(|&y| y < x)))
+    }
+
+    //Lower median of the range (the smaller of the two middle values when the length is even),
+    //None if the range is empty. Panics if the range is reversed or out of bounds.
+    pub fn median(&self, range: impl RangeBounds<usize>) -> Option<T> {
+        self.try_median(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_median(&self, range: impl RangeBounds<usize>) -> Result<Option<T>, RangeError> {
+        let range = to_half_open(range, self.size)?;
+        match range.len() {
+            0 => Ok(None),
+            len => self.try_kth_smallest(range, (len - 1) / 2),
+        }
+    }
+
+    //Sums count over the sorted values of the nodes covering the range, going up from the leaves.
+    fn count_where(&self, range: Range<usize>, count: impl Fn(&[T]) -> usize) -> usize {
+        let (mut left, mut right) = (range.start + self.size, range.end + self.size);
+        let mut total = 0;
+        while left < right {
+            if left % 2 == 1 {
+                total += count(&self.nodes[left]);
+                left += 1;
+            }
+            if right % 2 == 1 {
+                right -= 1;
+                total += count(&self.nodes[right]);
+            }
+            left /= 2;
+            right /= 2;
+        }
+        total
+    }
+}
+
+fn merge<T: Ord + Copy>(left: &[T], right: &[T]) -> Vec<T> {
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] <= right[j] {
+            merged.push(left[i]);
+            i += 1;
+        } else {
+            merged.push(right[j]);
+            j += 1;
+        }
+    }
+    merged.extend_from_slice(&left[i..]);
+    merged.extend_from_slice(&right[j..]);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::wavelet::WaveletTree;
+
+    #[test]
+    fn test_same_answers_as_wavelet_tree() {
+        let mut rng = Rng::new(50);
+        for n in [0, 1, 2, 3, 7, 8, 9, 100] {
+            let arr: Vec<i32> = (0..n).map(|_| rng.below(20) as i32).collect();
+            let merge_sort = MergeSortTree::new(&arr);
+            let wavelet = WaveletTree::new(&arr);
+            for start in 0..=n {
+                for end in start..=n {
+                    let x = rng.below(22) as i32 - 1;
+                    let k = rng.below(end - start + 1);
+                    assert_eq!(
+                        merge_sort.kth_smallest(start..end, k),
+                        wavelet.kth_smallest(start..end, k)
+                    );
+                    assert_eq!(
+                        merge_sort.count_less(start..end, x),
+                        wavelet.count_less(start..end, x)
+                    );
+                    assert_eq!(merge_sort.median(start..end), wavelet.median(start..end));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let tree = MergeSortTree::new(&[3, 1, 2]);
+        assert_eq!(tree.nodes[1], [1, 2, 3]);
+        assert!(tree.try_kth_smallest(4.., 0).is_err());
+        assert!(tree.try_count_less(..=3, 0).is_err());
+        assert_eq!(tree.median(..), Some(2));
+        assert_eq!(tree.median(..2), Some(1));
+        assert_eq!(tree.kth_smallest(1..1, 0), None);
+    }
+}
@@ -0,0 +1,374 @@
+//Standard monoids and actions for LazySegmentTree.
+//Each action implements Action only for the monoids it distributes over, for example ChMin works with
+//Max and Min but not with Sum. A pair of monoids (A, B) is a monoid too, for the actions that work on both.
+
+use crate::lazy::{Action, Monoid};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops;
+
+//Integer types usable as values of the standard monoids.
+pub trait Num: Copy + Ord + fmt::Debug + ops::Add<Output = Self> + ops::Mul<Output = Self> {
+    const ZERO: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    //Converts the length of a range, needed to apply an action to a sum.
+    fn from_len(len: usize) -> Self;
+}
+
+macro_rules! impl_num {
+    ($($t:ty),*) => {
+        $(
+            impl Num for $t {
+                const ZERO: Self = 0;
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn from_len(len: usize) -> Self {
+                    len as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_num!(i32, i64, i128, isize, u32, u64, usize);
+
+//Maximum of the range, the identity is T::MIN.
+#[derive(Debug)]
+pub struct Max<T>(PhantomData<T>);
+
+//Minimum of the range, the identity is T::MAX.
+#[derive(Debug)]
+pub struct Min<T>(PhantomData<T>);
+
+//Minimum and maximum of the range together, as the pair (min, max). The identity is (T::MAX, T::MIN).
+#[derive(Debug)]
+pub struct MinMax<T>(PhantomData<T>);
+
+//Sum of the range, the identity is 0.
+#[derive(Debug)]
+pub struct Sum<T>(PhantomData<T>);
+
+//Maximum of the range with the number of positions holding it and the strict second maximum, see MaxInfo.
+#[derive(Debug)]
+pub struct MaxCount<T>(PhantomData<T>);
+
+//Value of MaxCount. The identity, the value of an empty range, has count == 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxInfo<T> {
+    pub max: T,
+    //How many positions hold max.
+    pub count: usize,
+    //Largest value strictly smaller than max, None if every position holds max.
+    pub second_max: Option<T>,
+}
+
+impl<T> MaxInfo<T> {
+    //Value of a single position.
+    pub fn leaf(value: T) -> Self {
+        MaxInfo {
+            max: value,
+            count: 1,
+            second_max: None,
+        }
+    }
+}
+
+impl<T: Num> Monoid for Max<T> {
+    type Value = T;
+
+    fn identity() -> T {
+        T::MIN
+    }
+
+    fn combine(left: &T, right: &T) -> T {
+        *left.max(right)
+    }
+}
+
+impl<T: Num> Monoid for Min<T> {
+    type Value = T;
+
+    fn identity() -> T {
+        T::MAX
+    }
+
+    fn combine(left: &T, right: &T) -> T {
+        *left.min(right)
+    }
+}
+
+impl<T: Num> Monoid for MinMax<T> {
+    type Value = (T, T);
+
+    fn identity() -> (T, T) {
+        (T::MAX, T::MIN)
+    }
+
+    fn combine(left: &(T, T), right: &(T, T)) -> (T, T) {
+        (left.0.min(right.0), left.1.max(right.1))
+    }
+}
+
+impl<T: Num> Monoid for Sum<T> {
+    type Value = T;
+
+    fn identity() -> T {
+        T::ZERO
+    }
+
+    fn combine(left: &T, right: &T) -> T {
+        *left + *right
+    }
+}
+
+impl<T: Num> Monoid for MaxCount<T> {
+    type Value = MaxInfo<T>;
+
+    fn identity() -> MaxInfo<T> {
+        MaxInfo {
+            max: T::MIN,
+            count: 0,
+            second_max: None,
+        }
+    }
+
+    fn combine(left: &MaxInfo<T>, right: &MaxInfo<T>) -> MaxInfo<T> {
+        let max = left.max.max(right.max);
+        //The empty side must not add its T::MIN as a second maximum.
+        let below_max = |info: &MaxInfo<T>| {
+            if info.count > 0 && info.max < max {
+                Some(info.max)
+            } else {
+                info.second_max
+            }
+        };
+        let count = |info: &MaxInfo<T>| if info.max == max { info.count } else { 0 };
+        MaxInfo {
+            max,
+            count: count(left) + count(right),
+            second_max: below_max(left).max(below_max(right)),
+        }
+    }
+}
+
+//Two monoids side by side, every action that works on both works on the pair.
+impl<A: Monoid, B: Monoid> Monoid for (A, B) {
+    type Value = (A::Value, B::Value);
+
+    fn identity() -> Self::Value {
+        (A::identity(), B::identity())
+    }
+
+    fn combine(left: &Self::Value, right: &Self::Value) -> Self::Value {
+        (A::combine(&left.0, &right.0), B::combine(&left.1, &right.1))
+    }
+}
+
+impl<A: Monoid, B: Monoid, X: Action<A> + Action<B>> Action<(A, B)> for X {
+    fn apply(&self, value: &(A::Value, B::Value), len: usize) -> (A::Value, B::Value) {
+        (
+            Action::<A>::apply(self, &value.0, len),
+            Action::<B>::apply(self, &value.1, len),
+        )
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Action::<A>::compose(self, earlier)
+    }
+
+    fn breaks_on(&self, value: &(A::Value, B::Value)) -> bool {
+        Action::<A>::breaks_on(self, &value.0) || Action::<B>::breaks_on(self, &value.1)
+    }
+}
+
+//Replaces every element a[i] with min(a[i], t).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChMin<T>(pub T);
+
+//Replaces every element a[i] with max(a[i], t).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChMax<T>(pub T);
+
+//Adds t to every element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Add<T>(pub T);
+
+//Sets every element to t.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assign<T>(pub T);
+
+impl<T: Num> Action<Max<T>> for ChMin<T> {
+    fn apply(&self, value: &T, _len: usize) -> T {
+        *value.min(&self.0)
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        ChMin(self.0.min(earlier.0))
+    }
+}
+
+impl<T: Num> Action<Min<T>> for ChMin<T> {
+    fn apply(&self, value: &T, _len: usize) -> T {
+        *value.min(&self.0)
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        ChMin(self.0.min(earlier.0))
+    }
+}
+
+impl<T: Num> Action<MinMax<T>> for ChMin<T> {
+    fn apply(&self, value: &(T, T), _len: usize) -> (T, T) {
+        (value.0.min(self.0), value.1.min(self.0))
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        ChMin(self.0.min(earlier.0))
+    }
+}
+
+//A chmin only lowers the positions holding the max when second max < t, then the count does not change.
+//Otherwise it breaks and goes down (Segment Tree Beats, amortised O(log n) per update).
+impl<T: Num> Action<MaxCount<T>> for ChMin<T> {
+    fn apply(&self, value: &MaxInfo<T>, _len: usize) -> MaxInfo<T> {
+        MaxInfo {
+            max: value.max.min(self.0),
+            ..*value
+        }
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        ChMin(self.0.min(earlier.0))
+    }
+
+    fn breaks_on(&self, value: &MaxInfo<T>) -> bool {
+        value.second_max.is_some_and(|second| second >= self.0)
+    }
+}
+
+impl<T: Num> Action<Max<T>> for ChMax<T> {
+    fn apply(&self, value: &T, _len: usize) -> T {
+        *value.max(&self.0)
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        ChMax(self.0.max(earlier.0))
+    }
+}
+
+impl<T: Num> Action<Min<T>> for ChMax<T> {
+    fn apply(&self, value: &T, _len: usize) -> T {
+        *value.max(&self.0)
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        ChMax(self.0.max(earlier.0))
+    }
+}
+
+impl<T: Num> Action<MinMax<T>> for ChMax<T> {
+    fn apply(&self, value: &(T, T), _len: usize) -> (T, T) {
+        (value.0.max(self.0), value.1.max(self.0))
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        ChMax(self.0.max(earlier.0))
+    }
+}
+
+impl<T: Num> Action<Max<T>> for Add<T> {
+    fn apply(&self, value: &T, _len: usize) -> T {
+        *value + self.0
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+impl<T: Num> Action<Min<T>> for Add<T> {
+    fn apply(&self, value: &T, _len: usize) -> T {
+        *value + self.0
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+impl<T: Num> Action<MinMax<T>> for Add<T> {
+    fn apply(&self, value: &(T, T), _len: usize) -> (T, T) {
+        (value.0 + self.0, value.1 + self.0)
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+impl<T: Num> Action<MaxCount<T>> for Add<T> {
+    fn apply(&self, value: &MaxInfo<T>, _len: usize) -> MaxInfo<T> {
+        MaxInfo {
+            max: value.max + self.0,
+            count: value.count,
+            second_max: value.second_max.map(|second| second + self.0),
+        }
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+impl<T: Num> Action<Sum<T>> for Add<T> {
+    fn apply(&self, value: &T, len: usize) -> T {
+        *value + self.0 * T::from_len(len)
+    }
+
+    fn compose(&self, earlier: &Self) -> Self {
+        Add(self.0 + earlier.0)
+    }
+}
+
+impl<T: Num> Action<Max<T>> for Assign<T> {
+    fn apply(&self, _value: &T, _len: usize) -> T {
+        self.0
+    }
+
+    fn compose(&self, _earlier: &Self) -> Self {
+        *self
+    }
+}
+
+impl<T: Num> Action<Min<T>> for Assign<T> {
+    fn apply(&self, _value: &T, _len: usize) -> T {
+        self.0
+    }
+
+    fn compose(&self, _earlier: &Self) -> Self {
+        *self
+    }
+}
+
+impl<T: Num> Action<MinMax<T>> for Assign<T> {
+    fn apply(&self, _value: &(T, T), _len: usize) -> (T, T) {
+        (self.0, self.0)
+    }
+
+    fn compose(&self, _earlier: &Self) -> Self {
+        *self
+    }
+}
+
+impl<T: Num> Action<Sum<T>> for Assign<T> {
+    fn apply(&self, _value: &T, len: usize) -> T {
+        self.0 * T::from_len(len)
+    }
+
+    fn compose(&self, _earlier: &Self) -> Self {
+        *self
+    }
+}
@@ -0,0 +1,181 @@
+//Range handling shared by all the trees.
+//Every query and update takes any impl RangeBounds<usize> (a..b, a..=b, .., ...) over 0 indexed positions,
+//which is converted to the half-open range [start, end) and validated here.
+//An empty range such as a..a is valid, as for slices: queries on it give the empty result and updates do nothing.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::{Bound, Range, RangeBounds, RangeInclusive};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeError {
+    //The range starts after its end.
+    Reversed { start: usize, end: usize },
+    //The range goes past the last element.
+    OutOfBounds { end: usize, len: usize },
+    //The range of coordinates starts after its end.
+    ReversedCoordinates { start: i64, end: i64 },
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::Reversed { start, end } => {
+                write!(f, "range {}..{} starts after its end", start, end)
+            }
+            RangeError::OutOfBounds { end, len } => {
+                write!(f, "range end {} is out of bounds for length {}", end, len)
+            }
+            RangeError::ReversedCoordinates { start, end } => {
+                write!(
+                    f,
+                    "coordinate range {}..={} starts after its end",
+                    start, end
+                )
+            }
+        }
+    }
+}
+
+impl Error for RangeError {}
+
+//Converts range to the half-open range [start, end) and checks it against the number of elements len.
+pub fn to_half_open(
+    range: impl RangeBounds<usize>,
+    len: usize,
+) -> Result<Range<usize>, RangeError> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.saturating_add(1),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    if end > len {
+        return Err(RangeError::OutOfBounds { end, len });
+    }
+    if start > end {
+        if start > len {
+            return Err(RangeError::OutOfBounds { end: start, len });
+        }
+        return Err(RangeError::Reversed { start, end });
+    }
+    Ok(start..end)
+}
+
+//Converts a range of i64 coordinates to the inclusive range [first, last], None if the range is empty.
+//The trees over coordinates cover the whole i64 domain, so the range can never be out of bounds.
+pub fn to_inclusive(
+    range: impl RangeBounds<i64>,
+) -> Result<Option<RangeInclusive<i64>>, RangeError> {
+    //i128 so that Excluded(i64::MAX) and Excluded(i64::MIN) do not overflow.
+    let first = match range.start_bound() {
+        Bound::Included(&start) => start as i128,
+        Bound::Excluded(&start) => start as i128 + 1,
+        Bound::Unbounded => i64::MIN as i128,
+    };
+    let last = match range.end_bound() {
+        Bound::Included(&end) => end as i128,
+        Bound::Excluded(&end) => end as i128 - 1,
+        Bound::Unbounded => i64::MAX as i128,
+    };
+
+    if first > last + 1 {
+        return Err(RangeError::ReversedCoordinates {
+            start: first.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            end: last.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+        });
+    }
+    if first == last + 1 {
+        return Ok(None);
+    }
+    Ok(Some(first as i64..=last as i64))
+}
+
+//Adapter for the input files, whose ranges are 1 indexed and inclusive.
+//Converts [first, last] to the 0 indexed half-open range. Position 0 does not exist in 1 based indexing,
+//so first = 0 gives a range that is always out of bounds instead of underflowing.
+pub fn one_based(first: usize, last: usize) -> Range<usize> {
+    match first.checked_sub(1) {
+        Some(start) => start..last,
+        None => usize::MAX..usize::MAX,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_half_open() {
+        assert_eq!(to_half_open(1..3, 5), Ok(1..3));
+        assert_eq!(to_half_open(1..=3, 5), Ok(1..4));
+        assert_eq!(to_half_open(.., 5), Ok(0..5));
+        assert_eq!(to_half_open(2.., 5), Ok(2..5));
+        assert_eq!(to_half_open(..=0, 5), Ok(0..1));
+        assert_eq!(
+            to_half_open((Bound::Excluded(1), Bound::Included(2)), 5),
+            Ok(2..3)
+        );
+
+        //Empty ranges are fine, also at the end and on an empty tree.
+        assert_eq!(to_half_open(3..3, 5), Ok(3..3));
+        assert_eq!(to_half_open(5.., 5), Ok(5..5));
+        assert_eq!(to_half_open(.., 0), Ok(0..0));
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 4..2;
+        assert_eq!(
+            to_half_open(reversed, 5),
+            Err(RangeError::Reversed { start: 4, end: 2 })
+        );
+        assert_eq!(
+            to_half_open(0..=5, 5),
+            Err(RangeError::OutOfBounds { end: 6, len: 5 })
+        );
+        assert_eq!(
+            to_half_open(6.., 5),
+            Err(RangeError::OutOfBounds { end: 6, len: 5 })
+        );
+        assert_eq!(
+            to_half_open(..usize::MAX, 5),
+            Err(RangeError::OutOfBounds {
+                end: usize::MAX,
+                len: 5
+            })
+        );
+        assert!(to_half_open(..=usize::MAX, 5).is_err());
+    }
+
+    #[test]
+    fn test_to_inclusive() {
+        assert_eq!(to_inclusive(1..3), Ok(Some(1..=2)));
+        assert_eq!(to_inclusive(-5..=-5), Ok(Some(-5..=-5)));
+        assert_eq!(to_inclusive(..), Ok(Some(i64::MIN..=i64::MAX)));
+        assert_eq!(to_inclusive(..i64::MIN + 1), Ok(Some(i64::MIN..=i64::MIN)));
+        assert_eq!(to_inclusive(3..3), Ok(None));
+        assert_eq!(to_inclusive(..i64::MIN), Ok(None));
+        assert_eq!(
+            to_inclusive((Bound::Excluded(i64::MAX), Bound::Unbounded)),
+            Ok(None)
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..=1;
+        assert_eq!(
+            to_inclusive(reversed),
+            Err(RangeError::ReversedCoordinates { start: 3, end: 1 })
+        );
+    }
+
+    #[test]
+    fn test_one_based() {
+        assert_eq!(one_based(1, 5), 0..5);
+        assert_eq!(one_based(3, 3), 2..3);
+        assert!(to_half_open(one_based(0, 3), 5).is_err());
+        assert_eq!(to_half_open(one_based(1, 5), 5), Ok(0..5));
+    }
+}
@@ -0,0 +1,446 @@
+//Implicit treap: a sequence that supports inserting and erasing in the middle, splitting, concatenating and
+//reversing ranges, with the range max queries and range chmin updates of SegmentTree.
+//The key of a node is its position, given implicitly by the sizes of the subtrees on its left, and the random
+//priorities keep the tree a heap on them, so its depth is O(log n) in expectation. Every operation on a range
+//splits the range out, works on the root of the middle treap and concatenates the three parts back,
+//in expected O(log n).
+
+use crate::range::{to_half_open, RangeError};
+use crate::rng::Rng;
+use std::ops::{Range, RangeBounds};
+
+type Link = Option<Box<Node>>;
+
+#[derive(Debug)]
+struct Node {
+    value: i32,
+    //Max of the subtree, with the tags of this node already applied.
+    max: i32,
+    size: usize,
+    priority: u64,
+    //Pending updates of the children: the chmin to apply to them and whether they still have to be reversed.
+    //The children of a reversed node are already swapped, only their own subtrees are not.
+    chmin: Option<i32>,
+    reversed: bool,
+    left: Link,
+    right: Link,
+}
+
+impl Node {
+    fn new(value: i32, priority: u64) -> Box<Node> {
+        Box::new(Node {
+            value,
+            max: value,
+            size: 1,
+            priority,
+            chmin: None,
+            reversed: false,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn apply_chmin(&mut self, t: i32) {
+        self.value = self.value.min(t);
+        self.max = self.max.min(t);
+        self.chmin = Some(self.chmin.map_or(t, |pending| pending.min(t)));
+    }
+
+    fn apply_reverse(&mut self) {
+        std::mem::swap(&mut self.left, &mut self.right);
+        self.reversed = !self.reversed;
+    }
+
+    //Moves the pending updates to the children.
+    fn push(&mut self) {
+        let chmin = self.chmin.take();
+        let reversed = std::mem::take(&mut self.reversed);
+        for child in [&mut self.left, &mut self.right].into_iter().flatten() {
+            if let Some(t) = chmin {
+                child.apply_chmin(t);
+            }
+            if reversed {
+                child.apply_reverse();
+            }
+        }
+    }
+
+    fn pull(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+        self.max = [max(&self.left), max(&self.right)]
+            .into_iter()
+            .flatten()
+            .fold(self.value, i32::max);
+    }
+}
+
+fn size(link: &Link) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn max(link: &Link) -> Option<i32> {
+    link.as_ref().map(|node| node.max)
+}
+
+//Splits the sequence of link into its first k elements and the others.
+fn split(link: Link, k: usize) -> (Link, Link) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    node.push();
+    if k <= size(&node.left) {
+        let (left, right) = split(node.left.take(), k);
+        node.left = right;
+        node.pull();
+        (left, Some(node))
+    } else {
+        let (left, right) = split(node.right.take(), k - size(&node.left) - 1);
+        node.right = left;
+        node.pull();
+        (Some(node), right)
+    }
+}
+
+//The sequence of left followed by the one of right, the root is the node with the highest priority.
+fn merge(left: Link, right: Link) -> Link {
+    match (left, right) {
+        (None, link) | (link, None) => link,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.push();
+                left.right = merge(left.right.take(), Some(right));
+                left.pull();
+                Some(left)
+            } else {
+                right.push();
+                right.left = merge(Some(left), right.left.take());
+                right.pull();
+                Some(right)
+            }
+        }
+    }
+}
+
+//The queries do not push: they carry down the chmin and the reversal still pending from the ancestors.
+//A pending reversal swaps the children, the tags of the node are added to the pending ones for the nodes below.
+fn below(node: &Node, chmin: Option<i32>, reversed: bool) -> (Option<i32>, bool) {
+    let chmin = match (chmin, node.chmin) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    (chmin, reversed != node.reversed)
+}
+
+fn children(node: &Node, reversed: bool) -> (&Link, &Link) {
+    if reversed {
+        (&node.right, &node.left)
+    } else {
+        (&node.left, &node.right)
+    }
+}
+
+fn capped(value: i32, chmin: Option<i32>) -> i32 {
+    chmin.map_or(value, |t| value.min(t))
+}
+
+//Max of the positions start..end of the subtree.
+fn range_max(
+    link: &Link,
+    start: usize,
+    end: usize,
+    chmin: Option<i32>,
+    reversed: bool,
+) -> Option<i32> {
+    let node = link.as_ref()?;
+    if start >= end {
+        return None;
+    }
+    if start == 0 && end >= node.size {
+        return Some(capped(node.max, chmin));
+    }
+    let (first, second) = children(node, reversed);
+    let (chmin_below, reversed_below) = below(node, chmin, reversed);
+    let k = size(first);
+    let mut max = range_max(first, start, end.min(k), chmin_below, reversed_below);
+    if start <= k && k < end {
+        max = max.max(Some(capped(node.value, chmin)));
+    }
+    max.max(range_max(
+        second,
+        start.saturating_sub(k + 1),
+        end.saturating_sub(k + 1),
+        chmin_below,
+        reversed_below,
+    ))
+}
+
+//Appends the values of the subtree in order.
+fn collect(link: &Link, chmin: Option<i32>, reversed: bool, values: &mut Vec<i32>) {
+    if let Some(node) = link {
+        let (first, second) = children(node, reversed);
+        let (chmin_below, reversed_below) = below(node, chmin, reversed);
+        collect(first, chmin_below, reversed_below, values);
+        values.push(capped(node.value, chmin));
+        collect(second, chmin_below, reversed_below, values);
+    }
+}
+
+#[derive(Debug)]
+pub struct ImplicitTreap {
+    root: Link,
+    rng: Rng,
+}
+
+impl ImplicitTreap {
+    pub fn new(array: &[i32]) -> Self {
+        let mut treap = ImplicitTreap {
+            root: None,
+            rng: Rng::new(0x7265_6170),
+        };
+        for &value in array {
+            let node = Node::new(value, treap.rng.next_u64());
+            treap.root = merge(treap.root.take(), Some(node));
+        }
+        treap
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    //The current sequence, with every pending update applied. O(n).
+    pub fn to_vec(&self) -> Vec<i32> {
+        let mut values = Vec::with_capacity(self.len());
+        collect(&self.root, None, false, &mut values);
+        values
+    }
+
+    //Inserts value before the position pos, pos == len appends it. Panics if pos > len.
+    pub fn insert(&mut self, pos: usize, value: i32) {
+        self.try_insert(pos, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_insert(&mut self, pos: usize, value: i32) -> Result<(), RangeError> {
+        to_half_open(pos..pos, self.len())?;
+        let (left, right) = split(self.root.take(), pos);
+        let node = Node::new(value, self.rng.next_u64());
+        self.root = merge(merge(left, Some(node)), right);
+        Ok(())
+    }
+
+    //Removes the value at the position pos and returns it, the values after it shift left. Panics if pos >= len.
+    pub fn erase(&mut self, pos: usize) -> i32 {
+        self.try_erase(pos)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_erase(&mut self, pos: usize) -> Result<i32, RangeError> {
+        to_half_open(pos..=pos, self.len())?;
+        let (left, rest) = split(self.root.take(), pos);
+        let (node, right) = split(rest, 1);
+        self.root = merge(left, right);
+        Ok(node.map(|node| node.value).unwrap())
+    }
+
+    //Splits the sequence into the first pos values and the others. Panics if pos > len.
+    pub fn split_at(mut self, pos: usize) -> (ImplicitTreap, ImplicitTreap) {
+        to_half_open(pos..pos, self.len()).unwrap_or_else(|error| panic!("{}", error));
+        let (left, right) = split(self.root.take(), pos);
+        //The second half draws its priorities from a new generator, seeded by the first one.
+        let rng = Rng::new(self.rng.next_u64());
+        (
+            ImplicitTreap { root: left, ..self },
+            ImplicitTreap { root: right, rng },
+        )
+    }
+
+    //The values of self followed by the ones of other.
+    pub fn concat(mut self, mut other: ImplicitTreap) -> ImplicitTreap {
+        self.root = merge(self.root.take(), other.root.take());
+        self
+    }
+
+    //Reverses the order of the values in the range. Panics if the range is reversed or out of bounds.
+    pub fn reverse(&mut self, range: impl RangeBounds<usize>) {
+        self.try_reverse(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_reverse(&mut self, range: impl RangeBounds<usize>) -> Result<(), RangeError> {
+        let range = to_half_open(range, self.len())?;
+        self.with_range(range, |node| node.apply_reverse());
+        Ok(())
+    }
+
+    //Max in the range, positions are 0 indexed. None if the range is empty, panics if it is reversed or out of bounds.
+    pub fn max_query(&self, range: impl RangeBounds<usize>) -> Option<i32> {
+        self.try_max_query(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    //Goes down the two borders of the range without splitting, so the queries take &self as in SegmentTree.
+    pub fn try_max_query(&self, range: impl RangeBounds<usize>) -> Result<Option<i32>, RangeError> {
+        let range = to_half_open(range, self.len())?;
+        Ok(range_max(&self.root, range.start, range.end, None, false))
+    }
+
+    //Sets a[i] = min(a[i], new_val) for every i in the range. Panics if the range is reversed or out of bounds.
+    pub fn update(&mut self, range: impl RangeBounds<usize>, new_val: i32) {
+        self.try_update(range, new_val)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_update(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        new_val: i32,
+    ) -> Result<(), RangeError> {
+        let range = to_half_open(range, self.len())?;
+        self.with_range(range, |node| node.apply_chmin(new_val));
+        Ok(())
+    }
+
+    //Splits out the treap of the range, calls f on its root if it is not empty and puts the three parts back together.
+    fn with_range(&mut self, range: Range<usize>, f: impl FnOnce(&mut Node)) {
+        let (left, rest) = split(self.root.take(), range.start);
+        let (mut middle, right) = split(rest, range.len());
+        if let Some(node) = middle.as_mut() {
+            f(node);
+        }
+        self.root = merge(merge(left, middle), right);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Depth of the deepest node, to check that the random priorities keep the tree balanced.
+    fn depth(link: &Link) -> usize {
+        link.as_ref()
+            .map_or(0, |node| 1 + depth(&node.left).max(depth(&node.right)))
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = Rng::new(49);
+        for _ in 0..30 {
+            let mut naive: Vec<i32> = (0..rng.below(30)).map(|_| rng.below(100) as i32).collect();
+            let mut treap = ImplicitTreap::new(&naive);
+
+            for _ in 0..300 {
+                let n = naive.len();
+                let a = rng.below(n + 1);
+                let b = rng.below(n + 1);
+                let range = a.min(b)..a.max(b);
+                let value = rng.below(100) as i32;
+                match rng.below(6) {
+                    0 => {
+                        treap.insert(a, value);
+                        naive.insert(a, value);
+                    }
+                    1 if n > 0 => {
+                        let pos = rng.below(n);
+                        assert_eq!(treap.erase(pos), naive.remove(pos));
+                    }
+                    2 => {
+                        treap.reverse(range.clone());
+                        naive[range].reverse();
+                    }
+                    3 => {
+                        treap.update(range.clone(), value);
+                        naive[range].iter_mut().for_each(|x| *x = (*x).min(value));
+                    }
+                    _ => assert_eq!(
+                        treap.max_query(range.clone()),
+                        naive[range].iter().max().copied()
+                    ),
+                }
+                assert_eq!(treap.len(), naive.len());
+            }
+            assert_eq!(treap.to_vec(), naive);
+        }
+    }
+
+    #[test]
+    fn test_split_and_concat() {
+        let values: Vec<i32> = (0..100).collect();
+        let mut treap = ImplicitTreap::new(&values);
+        treap.update(50.., 60);
+        treap.reverse(..20);
+
+        let (left, mut right) = treap.split_at(30);
+        assert_eq!(left.len(), 30);
+        assert_eq!(left.max_query(..), Some(29));
+        assert_eq!(right.max_query(..), Some(60));
+        right.insert(0, 1000);
+
+        //The halves can be joined in the other order, with the pending tags of both.
+        let joined = right.concat(left);
+        let mut expected: Vec<i32> = values.iter().map(|&x| x.min(60)).collect();
+        expected[..20].reverse();
+        expected.insert(30, 1000);
+        expected.rotate_left(30);
+        assert_eq!(joined.to_vec(), expected);
+        assert_eq!(joined.max_query(..71), Some(1000));
+        assert_eq!(joined.max_query(71..), Some(29));
+
+        let (empty, all) = joined.split_at(0);
+        assert!(empty.is_empty());
+        assert_eq!(all.len(), 101);
+    }
+
+    //Same updates and queries of SegmentTree, with the same answers while nothing is inserted or moved.
+    #[test]
+    fn test_same_answers_as_segment_tree() {
+        let mut rng = Rng::new(49);
+        let values: Vec<i32> = (0..200).map(|_| rng.below(1000) as i32).collect();
+        let mut treap = ImplicitTreap::new(&values);
+        let mut tree = crate::SegmentTree::new(&values);
+        for _ in 0..500 {
+            let a = rng.below(201);
+            let b = rng.below(201);
+            let t = rng.below(1000) as i32;
+            treap.update(a.min(b)..a.max(b), t);
+            tree.update(a.min(b)..a.max(b), t);
+            let c = rng.below(201);
+            let d = rng.below(201);
+            let range = c.min(d)..c.max(d);
+            assert_eq!(treap.max_query(range.clone()), tree.max_query(range));
+        }
+    }
+
+    #[test]
+    fn test_depth_is_logarithmic() {
+        let mut treap = ImplicitTreap::new(&[]);
+        //Always inserting at the front would make a list out of an unbalanced binary search tree.
+        for i in 0..1 << 14 {
+            treap.insert(0, i);
+        }
+        assert!(depth(&treap.root) < 60, "depth {}", depth(&treap.root));
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut treap = ImplicitTreap::new(&[3, 1, 2]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert!(treap.try_max_query(reversed.clone()).is_err());
+        assert!(treap.try_update(..4, 0).is_err());
+        assert!(treap.try_reverse(reversed).is_err());
+        assert!(treap.try_insert(4, 0).is_err());
+        assert!(treap.try_erase(3).is_err());
+        assert_eq!(treap.max_query(1..1), None);
+        treap.insert(3, 5);
+        assert_eq!(treap.to_vec(), [3, 1, 2, 5]);
+
+        let mut empty = ImplicitTreap::new(&[]);
+        assert_eq!(empty.max_query(..), None);
+        assert!(empty.try_erase(0).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//Wavelet tree for order statistics on a static array: the k-th smallest value of a range, the number of values
+//below a bound and the median, in O(log σ) where σ is the number of distinct values.
+//The values are replaced by their rank among the distinct values, and every level holds the ranks of the level
+//above stably partitioned by one bit, the highest first: the ranks with the bit 0 on the left, the others on the
+//right. A range of a level then maps to one range of the zeros and one of the ones of the next level, so a query
+//follows a single range from the top level down to the bottom, going left or right at every bit.
+
+use crate::range::{to_half_open, RangeError};
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug)]
+pub struct WaveletTree<T> {
+    size: usize,
+    //The distinct values in increasing order, the rank of a value is its index.
+    values: Vec<T>,
+    //zeros[level][i] is the number of ranks with the bit 0 among the first i of the level.
+    zeros: Vec<Vec<usize>>,
+}
+
+impl<T: Ord + Copy> WaveletTree<T> {
+    pub fn new(arr: &[T]) -> Self {
+        let mut values = arr.to_vec();
+        values.sort_unstable();
+        values.dedup();
+        //Bits of the largest rank, none if there is at most one distinct value.
+        let bits = (usize::BITS - values.len().saturating_sub(1).leading_zeros()) as usize;
+
+        let mut ranks: Vec<usize> = arr
+            .iter()
+            .map(|x| values.binary_search(x).unwrap())
+            .collect();
+        let mut zeros = Vec::with_capacity(bits);
+        for bit in (0..bits).rev() {
+            let mut counts = Vec::with_capacity(ranks.len() + 1);
+            counts.push(0);
+            for &rank in &ranks {
+                counts.push(counts.last().unwrap() + (rank >> bit & 1 == 0) as usize);
+            }
+            zeros.push(counts);
+            //Stable partition, the order of the level below.
+            let (mut level, ones): (Vec<usize>, Vec<usize>) =
+                ranks.iter().partition(|&&rank| rank >> bit & 1 == 0);
+            level.extend(ones);
+            ranks = level;
+        }
+        WaveletTree {
+            size: arr.len(),
+            values,
+            zeros,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    //Bytes taken by the distinct values and the prefix counts, apart from the Vec headers.
+    pub fn memory_bytes(&self) -> usize {
+        self.values.len() * std::mem::size_of::<T>()
+            + self.zeros.iter().map(Vec::len).sum::<usize>() * std::mem::size_of::<usize>()
+    }
+
+    //k-th smallest value in the range, counting from 0, None if the range has at most k values. O(log σ).
+    //Panics if the range is reversed or out of bounds.
+    pub fn kth_smallest(&self, range: impl RangeBounds<usize>, k: usize) -> Option<T> {
+        self.try_kth_smallest(range, k)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    //At every level the k-th smallest is among the zeros of the range if there are more than k of them,
+    //otherwise it is among the ones and the zeros are skipped.
+    pub fn try_kth_smallest(
+        &self,
+        range: impl RangeBounds<usize>,
+        k: usize,
+    ) -> Result<Option<T>, RangeError> {
+        let Range { mut start, mut end } = to_half_open(range, self.size)?;
+        if k >= end - start {
+            return Ok(None);
+        }
+        let mut k = k;
+        let mut rank = 0;
+        for counts in &self.zeros {
+            let (zeros_start, zeros_end) = (counts[start], counts[end]);
+            rank <<= 1;
+            if k < zeros_end - zeros_start {
+                (start, end) = (zeros_start, zeros_end);
+            } else {
+                k -= zeros_end - zeros_start;
+                let all_zeros = counts[self.size];
+                (start, end) = (all_zeros + start - zeros_start, all_zeros + end - zeros_end);
+                rank |= 1;
+            }
+        }
+        Ok(Some(self.values[rank]))
+    }
+
+    //Number of values in the range strictly smaller than x. O(log σ).
+    //Panics if the range is reversed or out of bounds.
+    pub fn count_less(&self, range: impl RangeBounds<usize>, x: T) -> usize {
+        self.try_count_less(range, x)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    //The values below x are the ranks below the rank bound of x. Going down the bits of bound, a 1 means that every
+    //rank of the range with a 0 there is smaller.
+    pub fn try_count_less(
+        &self,
+        range: impl RangeBounds<usize>,
+        x: T,
+    ) -> Result<usize, RangeError> {
+        let Range { mut start, mut end } = to_half_open(range, self.size)?;
+        let bound = self.values.partition_point(|&value| value < x);
+        if bound == self.values.len() {
+            return Ok(end - start);
+        }
+        let mut count = 0;
+        for (level, counts) in self.zeros.iter().enumerate() {
+            let bit = self.zeros.len() - 1 - level;
+            let (zeros_start, zeros_end) = (counts[start], counts[end]);
+            if bound >> bit & 1 == 0 {
+                (start, end) = (zeros_start, zeros_end);
+            } else {
+                count += zeros_end - zeros_start;
+                let all_zeros = counts[self.size];
+                (start, end) = (all_zeros + start - zeros_start, all_zeros + end - zeros_end);
+            }
+        }
+        Ok(count)
+    }
+
+    //Lower median of the range (the smaller of the two middle values when the length is even),
+    //None if the range is empty. Panics if the range is reversed or out of bounds.
+    pub fn median(&self, range: impl RangeBounds<usize>) -> Option<T> {
+        self.try_median(range)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_median(&self, range: impl RangeBounds<usize>) -> Result<Option<T>, RangeError> {
+        let range = to_half_open(range, self.size)?;
+        match range.len() {
+            0 => Ok(None),
+            len => self.try_kth_smallest(range, (len - 1) / 2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = Rng::new(50);
+        for _ in 0..40 {
+            let n = rng.below(60);
+            //Few distinct values, then many, so both shallow and deep trees are covered.
+            let spread = [1, 2, 5, 1000][rng.below(4)];
+            let arr: Vec<i64> = (0..n).map(|_| rng.below(spread) as i64 - 3).collect();
+            let tree = WaveletTree::new(&arr);
+
+            for _ in 0..100 {
+                let a = rng.below(n + 1);
+                let b = rng.below(n + 1);
+                let range = a.min(b)..a.max(b);
+                let mut sorted = arr[range.clone()].to_vec();
+                sorted.sort();
+
+                let k = rng.below(sorted.len() + 2);
+                assert_eq!(tree.kth_smallest(range.clone(), k), sorted.get(k).copied());
+                let x = rng.below(spread + 2) as i64 - 4;
+                assert_eq!(
+                    tree.count_less(range.clone(), x),
+                    sorted.iter().filter(|&&y| y < x).count()
+                );
+                let median = (!sorted.is_empty()).then(|| sorted[(sorted.len() - 1) / 2]);
+                assert_eq!(tree.median(range), median);
+            }
+        }
+    }
+
+    #[test]
+    fn test_levels() {
+        //5 distinct values, ranks 0..=4 need 3 bits.
+        let tree = WaveletTree::new(&[50, 10, 40, 20, 30, 10]);
+        assert_eq!(tree.zeros.len(), 3);
+        assert_eq!(tree.values, [10, 20, 30, 40, 50]);
+        assert_eq!(tree.kth_smallest(.., 0), Some(10));
+        assert_eq!(tree.kth_smallest(.., 1), Some(10));
+        assert_eq!(tree.kth_smallest(2..5, 2), Some(40));
+
+        let single = WaveletTree::new(&[7, 7, 7]);
+        assert!(single.zeros.is_empty());
+        assert_eq!(single.kth_smallest(1.., 1), Some(7));
+        assert_eq!(single.count_less(.., 7), 0);
+        assert_eq!(single.count_less(.., 8), 3);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let tree = WaveletTree::new(&[3, 1, 2]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 2..1;
+        assert!(tree.try_kth_smallest(reversed.clone(), 0).is_err());
+        assert!(tree.try_count_less(..4, 0).is_err());
+        assert!(tree.try_median(reversed).is_err());
+        assert_eq!(tree.median(1..1), None);
+        assert_eq!(tree.kth_smallest(.., 3), None);
+
+        let empty = WaveletTree::<i32>::new(&[]);
+        assert_eq!(empty.median(..), None);
+        assert_eq!(empty.count_less(.., 0), 0);
+    }
+}